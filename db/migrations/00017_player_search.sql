@@ -0,0 +1,10 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- unaccent() is only STABLE, an IMMUTABLE wrapper is needed to use it in indexes
+CREATE OR REPLACE FUNCTION immutable_unaccent(TEXT) RETURNS TEXT AS $$
+    SELECT public.unaccent('public.unaccent', $1)
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT;
+
+CREATE INDEX players_name_trgm_idx ON players USING GIN (immutable_unaccent(LOWER(name)) gin_trgm_ops);
+CREATE INDEX players_alias_trgm_idx ON players USING GIN (immutable_unaccent(LOWER(alias)) gin_trgm_ops);
@@ -0,0 +1,5 @@
+ALTER TABLE auth_tokens ADD COLUMN id SERIAL PRIMARY KEY;
+ALTER TABLE auth_tokens ADD COLUMN last_used TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL;
+ALTER TABLE auth_tokens ADD COLUMN ip INET;
+ALTER TABLE auth_tokens ADD COLUMN user_agent TEXT;
+UPDATE auth_tokens SET last_used = created;
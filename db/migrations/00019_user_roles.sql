@@ -0,0 +1,31 @@
+CREATE TYPE user_role AS ENUM (
+    'superuser',
+    'submission_reviewer',
+    'score_editor',
+    'player_editor',
+    'blog_author',
+    'standards_editor'
+);
+
+CREATE TABLE user_roles (
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    role user_role NOT NULL,
+    PRIMARY KEY (user_id, role)
+);
+
+-- is_superuser and is_staff are kept for compatibility, permissions are checked through user_roles
+INSERT INTO user_roles (user_id, role)
+SELECT id, 'superuser'::user_role FROM users WHERE is_superuser = TRUE;
+
+INSERT INTO user_roles (user_id, role)
+SELECT users.id, roles.role
+FROM users
+CROSS JOIN (
+    VALUES
+        ('submission_reviewer'::user_role),
+        ('score_editor'::user_role),
+        ('player_editor'::user_role),
+        ('blog_author'::user_role),
+        ('standards_editor'::user_role)
+) AS roles(role)
+WHERE users.is_staff = TRUE AND users.is_superuser = FALSE;
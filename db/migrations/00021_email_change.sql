@@ -0,0 +1,2 @@
+ALTER TYPE token_type ADD VALUE 'email_change';
+ALTER TABLE tokens ADD COLUMN new_email VARCHAR(254);
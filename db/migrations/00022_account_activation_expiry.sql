@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN created TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL;
@@ -0,0 +1,14 @@
+CREATE TABLE player_claims (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    player_id INTEGER REFERENCES players(id) ON DELETE CASCADE NOT NULL,
+    chadsoft_id BIGINT,
+    user_note TEXT,
+    status submission_status DEFAULT 'pending' NOT NULL,
+    submitted_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    reviewer_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    reviewer_note TEXT,
+    reviewed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX player_claims_one_open_per_user ON player_claims (user_id) WHERE status IN ('pending', 'on_hold');
@@ -0,0 +1,3 @@
+-- Password hashes are now PHC strings which embed the salt, the column is only kept
+-- for hashes in the old format until they get upgraded on login
+ALTER TABLE users ALTER COLUMN salt DROP NOT NULL;
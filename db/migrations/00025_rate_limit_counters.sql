@@ -0,0 +1,7 @@
+CREATE TABLE rate_limit_counters (
+    route VARCHAR(64) NOT NULL,
+    key VARCHAR(64) NOT NULL,
+    count INTEGER NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (route, key)
+);
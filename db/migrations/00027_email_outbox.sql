@@ -0,0 +1,18 @@
+CREATE TYPE email_status AS ENUM ('pending', 'sent', 'failed');
+
+CREATE TABLE email_outbox (
+    id SERIAL PRIMARY KEY,
+    recipient_name VARCHAR(150) NOT NULL,
+    recipient_email VARCHAR(254) NOT NULL,
+    subject TEXT NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT,
+    status email_status DEFAULT 'pending' NOT NULL,
+    attempts INTEGER DEFAULT 0 NOT NULL,
+    last_error TEXT,
+    created TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    next_attempt TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX email_outbox_pending_idx ON email_outbox (next_attempt) WHERE status = 'pending';
@@ -0,0 +1,9 @@
+CREATE TABLE notification_preferences (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    submission_reviews BOOLEAN DEFAULT FALSE NOT NULL,
+    lost_records BOOLEAN DEFAULT FALSE NOT NULL,
+    site_champions BOOLEAN DEFAULT FALSE NOT NULL
+);
+
+ALTER TABLE site_champs ADD COLUMN notified BOOLEAN DEFAULT FALSE NOT NULL;
+UPDATE site_champs SET notified = TRUE;
@@ -0,0 +1,32 @@
+CREATE TYPE webhook_event AS ENUM ('new_wr', 'new_regional_record', 'new_submission', 'blog_post_published');
+
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    events webhook_event[] NOT NULL,
+    is_active BOOLEAN DEFAULT TRUE NOT NULL,
+    created_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event webhook_event NOT NULL,
+    payload TEXT NOT NULL,
+    status webhook_delivery_status DEFAULT 'pending' NOT NULL,
+    attempts INTEGER DEFAULT 0 NOT NULL,
+    response_code INTEGER,
+    last_error TEXT,
+    created TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    next_attempt TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    delivered_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt) WHERE status = 'pending';
+
+ALTER TABLE blog_posts ADD COLUMN announced BOOLEAN DEFAULT FALSE NOT NULL;
+UPDATE blog_posts SET announced = TRUE;
@@ -0,0 +1,17 @@
+-- Posts are written in Markdown, content keeps the rendered HTML.
+-- Imported posts only have HTML, so their markdown stays empty until edited
+ALTER TABLE blog_posts ADD COLUMN markdown TEXT;
+ALTER TABLE blog_posts ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE blog_post_revisions (
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES blog_posts(id) ON DELETE CASCADE,
+    title VARCHAR(255) NOT NULL,
+    markdown TEXT NOT NULL,
+    is_published BOOLEAN NOT NULL,
+    published_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    editor_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX blog_post_revisions_post_id_idx ON blog_post_revisions (post_id);
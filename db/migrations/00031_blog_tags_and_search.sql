@@ -0,0 +1,12 @@
+ALTER TABLE blog_posts ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE blog_post_revisions ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Content is HTML, which the text search parser already skips tags of
+ALTER TABLE blog_posts ADD COLUMN search TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', title), 'A') ||
+    setweight(to_tsvector('english', content), 'B')
+) STORED;
+
+CREATE INDEX blog_posts_tags_idx ON blog_posts USING GIN (tags);
+CREATE INDEX blog_posts_search_idx ON blog_posts USING GIN (search);
+CREATE INDEX blog_posts_published_at_idx ON blog_posts (published_at DESC, id DESC);
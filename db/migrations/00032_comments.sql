@@ -0,0 +1,24 @@
+ALTER TYPE user_role ADD VALUE 'comment_moderator';
+
+-- A comment belongs to either a blog post or a score.
+-- Deleting a comment only hides it, so replies keep their place in the thread
+CREATE TABLE comments (
+    id SERIAL PRIMARY KEY,
+    blog_post_id INTEGER REFERENCES blog_posts(id) ON DELETE CASCADE,
+    score_id INTEGER REFERENCES scores(id) ON DELETE CASCADE,
+    parent_id INTEGER REFERENCES comments(id) ON DELETE CASCADE,
+    author_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    markdown TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    edited_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    removed_at TIMESTAMP WITH TIME ZONE,
+    removed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    removal_reason VARCHAR(255),
+    CHECK ((blog_post_id IS NULL) <> (score_id IS NULL))
+);
+
+CREATE INDEX comments_blog_post_id_idx ON comments (blog_post_id, created) WHERE blog_post_id IS NOT NULL;
+CREATE INDEX comments_score_id_idx ON comments (score_id, created) WHERE score_id IS NOT NULL;
+CREATE INDEX comments_author_id_idx ON comments (author_id);
@@ -0,0 +1,3 @@
+CREATE TYPE webhook_format AS ENUM ('generic', 'discord');
+
+ALTER TABLE webhooks ADD COLUMN format webhook_format DEFAULT 'generic' NOT NULL;
@@ -0,0 +1,17 @@
+-- Accepted submissions were not linked to the score they created
+UPDATE submissions
+SET score_id = (
+    SELECT scores.id
+    FROM scores
+    WHERE
+        scores.player_id = submissions.player_id AND
+        scores.track_id = submissions.track_id AND
+        scores.category = submissions.category AND
+        scores.is_lap = submissions.is_lap AND
+        scores.value = submissions.value
+    ORDER BY scores.id
+    LIMIT 1
+)
+WHERE score_id IS NULL AND status = 'accepted';
+
+CREATE INDEX submissions_score_id_idx ON submissions (score_id) WHERE score_id IS NOT NULL;
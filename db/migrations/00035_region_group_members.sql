@@ -0,0 +1,16 @@
+-- Groups can share regions, which a single parent_id can't express
+CREATE TABLE region_group_members (
+    group_id INTEGER NOT NULL REFERENCES regions(id) ON DELETE CASCADE,
+    region_id INTEGER NOT NULL REFERENCES regions(id) ON DELETE CASCADE,
+    PRIMARY KEY (group_id, region_id),
+    CHECK (group_id <> region_id)
+);
+
+CREATE INDEX region_group_members_region_id_idx ON region_group_members (region_id);
+
+-- Groups keep the regions that were already their children
+INSERT INTO region_group_members (group_id, region_id)
+SELECT regions.parent_id, regions.id
+FROM regions
+JOIN regions AS groups ON groups.id = regions.parent_id
+WHERE groups.region_type IN ('country_group', 'subnational_group');
@@ -0,0 +1,29 @@
+-- $1 - limit
+-- $2 - offset
+-- $3 - tag (nullable)
+-- $4 - full text search query, empty for none
+-- $5 - cursor, id of the last post already seen (nullable)
+
+SELECT
+    blog_posts.id, blog_posts.title,
+    blog_posts.content, blog_posts.is_published,
+    blog_posts.published_at, blog_posts.tags,
+    users.player_id AS author_id, users.username
+FROM blog_posts
+LEFT JOIN users ON users.id = blog_posts.author_id
+WHERE
+    blog_posts.is_published AND
+    blog_posts.published_at <= NOW() AND
+    ($3::TEXT IS NULL OR $3 = ANY(blog_posts.tags)) AND
+    ($4 = '' OR blog_posts.search @@ websearch_to_tsquery('english', $4)) AND
+    (
+        $5::INTEGER IS NULL OR
+        (blog_posts.published_at, blog_posts.id) < (
+            SELECT published_at, id FROM blog_posts WHERE id = $5
+        )
+    )
+ORDER BY
+    blog_posts.published_at DESC,
+    blog_posts.id DESC
+LIMIT $1
+OFFSET $2;
\ No newline at end of file
@@ -1,5 +1,7 @@
 -- $1 - limit
 -- $2 - only_records
+-- $3 - player_id (nullable)
+-- $4 - region_ids (nullable)
 
 SELECT
     scores.id AS s_id,
@@ -11,6 +13,8 @@ FROM scores
 LEFT JOIN players ON scores.player_id = players.id
 WHERE
     date IS NOT NULL AND
+    ($3::INTEGER IS NULL OR player_id = $3) AND
+    ($4::INTEGER[] IS NULL OR region_id = ANY($4)) AND
     (
         ($2 = TRUE AND was_wr = TRUE) OR
         ($2 = FALSE)
@@ -0,0 +1,29 @@
+-- $1 - limit
+-- $2 - player_id
+
+SELECT * FROM (
+    SELECT
+        scores.id AS s_id,
+        value, category,
+        is_lap, track_id,
+        date, players.id, name,
+        alias, region_id,
+        COALESCE(
+            (
+                SELECT MIN(COALESCE(submissions.reviewed_at, submissions.submitted_at))
+                FROM submissions
+                WHERE submissions.score_id = scores.id
+            ),
+            date::TIMESTAMP AT TIME ZONE 'UTC'
+        ) AS accepted_at
+    FROM scores
+    LEFT JOIN players ON scores.player_id = players.id
+    WHERE scores.player_id = $2
+) AS accepted
+WHERE accepted_at IS NOT NULL
+ORDER BY
+    accepted_at DESC,
+    track_id ASC,
+    category ASC,
+    is_lap ASC
+LIMIT $1;
@@ -0,0 +1,34 @@
+-- $1 - search query
+-- $2 - region_ids
+-- $3 - limit
+
+WITH search AS (
+    SELECT immutable_unaccent(LOWER($1)) AS query
+)
+SELECT id, name, alias, region_id
+FROM (
+    SELECT
+        id, name, alias, region_id,
+        (
+            starts_with(immutable_unaccent(LOWER(name)), search.query) OR
+            COALESCE(starts_with(immutable_unaccent(LOWER(alias)), search.query), FALSE)
+        ) AS is_prefix,
+        GREATEST(
+            similarity(immutable_unaccent(LOWER(name)), search.query),
+            COALESCE(similarity(immutable_unaccent(LOWER(alias)), search.query), 0)
+        ) AS relevance
+    FROM players, search
+    WHERE
+        region_id = ANY($2) AND
+        (
+            starts_with(immutable_unaccent(LOWER(name)), search.query) OR
+            starts_with(immutable_unaccent(LOWER(alias)), search.query) OR
+            immutable_unaccent(LOWER(name)) % search.query OR
+            immutable_unaccent(LOWER(alias)) % search.query
+        )
+) AS ranked
+ORDER BY
+    is_prefix DESC,
+    relevance DESC,
+    name ASC
+LIMIT $3;
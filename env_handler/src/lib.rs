@@ -89,6 +89,161 @@ pub struct EnvSettings {
     #[value = false]
     #[description = "Whether the TLS certificate for the SMTP server is valid or not"]
     pub smtp_tls_cert_valid: bool,
+
+    #[key = "EMAIL_TEMPLATES_DIR"]
+    #[value = "email_text"]
+    #[description = "Directory with the email templates, one subdirectory per locale"]
+    pub email_templates_dir: String,
+
+    #[key = "EMAIL_DEFAULT_LOCALE"]
+    #[value = "en"]
+    #[description = "Locale used for emails when a template doesn't exist in the user's locale"]
+    pub email_default_locale: String,
+
+    #[key = "EMAIL_OUTPUT_DIR"]
+    #[value = ""]
+    #[description = "If set, emails are written to this directory as .eml files instead of being sent through SMTP"]
+    pub email_output_dir: String,
+
+    #[key = "EMAIL_OUTBOX_INTERVAL"]
+    #[value = 10]
+    #[description = "Seconds between each check of the email outbox"]
+    pub email_outbox_interval: u64,
+
+    #[key = "EMAIL_MAX_ATTEMPTS"]
+    #[value = 8]
+    #[description = "Attempts at sending an email before it is marked as failed"]
+    pub email_max_attempts: u32,
+
+    #[key = "EMAIL_RETRY_DELAY"]
+    #[value = 60]
+    #[description = "Seconds before the first retry of an email, doubled after every failed attempt"]
+    pub email_retry_delay: u32,
+
+    #[key = "EMAIL_SENT_EXPIRY"]
+    #[value = 30]
+    #[description = "Days after which sent emails are deleted from the outbox"]
+    pub email_sent_expiry: u32,
+
+    #[key = "WEBHOOK_INTERVAL"]
+    #[value = 10]
+    #[description = "Seconds between each check for pending webhook deliveries"]
+    pub webhook_interval: u64,
+
+    #[key = "WEBHOOK_MAX_ATTEMPTS"]
+    #[value = 8]
+    #[description = "Attempts at delivering a webhook before it is marked as failed"]
+    pub webhook_max_attempts: u32,
+
+    #[key = "WEBHOOK_RETRY_DELAY"]
+    #[value = 30]
+    #[description = "Seconds before the first retry of a webhook delivery, doubled after every failed attempt"]
+    pub webhook_retry_delay: u32,
+
+    #[key = "WEBHOOK_TIMEOUT"]
+    #[value = 10]
+    #[description = "Seconds to wait for a webhook endpoint to respond"]
+    pub webhook_timeout: u64,
+
+    #[key = "WEBHOOK_DELIVERY_EXPIRY"]
+    #[value = 30]
+    #[description = "Days after which finished webhook deliveries are deleted"]
+    pub webhook_delivery_expiry: u32,
+
+    #[key = "ACTIVATION_TOKEN_EXPIRY"]
+    #[value = 48]
+    #[description = "Hours after which an account activation link expires"]
+    pub activation_token_expiry: u32,
+
+    #[key = "ACTIVATION_RESEND_COOLDOWN"]
+    #[value = 600]
+    #[description = "Seconds a user has to wait before another activation email can be sent"]
+    pub activation_resend_cooldown: u32,
+
+    #[key = "UNVERIFIED_ACCOUNT_EXPIRY"]
+    #[value = 30]
+    #[description = "Days after which accounts that were never activated get deleted"]
+    pub unverified_account_expiry: u32,
+
+    #[key = "PASSWORD_MIN_LENGTH"]
+    #[value = 9]
+    #[description = "Minimum amount of characters in a password"]
+    pub password_min_length: u32,
+
+    #[key = "PASSWORD_MAX_LENGTH"]
+    #[value = 128]
+    #[description = "Maximum amount of characters in a password"]
+    pub password_max_length: u32,
+
+    #[key = "PASSWORD_REQUIRE_UPPERCASE"]
+    #[value = true]
+    #[description = "Whether passwords must contain an uppercase character"]
+    pub password_require_uppercase: bool,
+
+    #[key = "PASSWORD_REQUIRE_LOWERCASE"]
+    #[value = true]
+    #[description = "Whether passwords must contain a lowercase character"]
+    pub password_require_lowercase: bool,
+
+    #[key = "PASSWORD_REQUIRE_NUMBER"]
+    #[value = true]
+    #[description = "Whether passwords must contain a number"]
+    pub password_require_number: bool,
+
+    #[key = "PASSWORD_REQUIRE_SPECIAL"]
+    #[value = true]
+    #[description = "Whether passwords must contain a special character"]
+    pub password_require_special: bool,
+
+    #[key = "PASSWORD_BREACH_LIST"]
+    #[value = ""]
+    #[description = "Path to a file of SHA-1 hashes of breached passwords, one per line, optionally followed by `:count`. Empty disables the check"]
+    pub password_breach_list: String,
+
+    #[key = "ARGON2_MEMORY_COST"]
+    #[value = 19456]
+    #[description = "Memory used to hash a password, in KiB. Stored hashes get upgraded on login when changed"]
+    pub argon2_memory_cost: u32,
+
+    #[key = "ARGON2_TIME_COST"]
+    #[value = 2]
+    #[description = "Iterations used to hash a password. Stored hashes get upgraded on login when changed"]
+    pub argon2_time_cost: u32,
+
+    #[key = "ARGON2_PARALLELISM"]
+    #[value = 1]
+    #[description = "Lanes used to hash a password. Stored hashes get upgraded on login when changed"]
+    pub argon2_parallelism: u32,
+
+    #[key = "RATE_LIMIT_POSTGRES"]
+    #[value = false]
+    #[description = "Whether rate limit counters are kept in Postgres instead of memory, so they are shared between instances and survive restarts"]
+    pub rate_limit_postgres: bool,
+
+    #[key = "RATE_LIMIT_REGISTER"]
+    #[value = "5/3600"]
+    #[description = "Requests per seconds allowed for each IP on account registration. Empty disables the limit"]
+    pub rate_limit_register: String,
+
+    #[key = "RATE_LIMIT_LOGIN"]
+    #[value = "30/600"]
+    #[description = "Requests per seconds allowed for each IP on login. Empty disables the limit"]
+    pub rate_limit_login: String,
+
+    #[key = "RATE_LIMIT_EMAIL"]
+    #[value = "5/3600"]
+    #[description = "Requests per seconds allowed for each IP and user on every route that sends emails. Empty disables the limit"]
+    pub rate_limit_email: String,
+
+    #[key = "RATE_LIMIT_SUBMISSIONS"]
+    #[value = "120/3600"]
+    #[description = "Requests per seconds allowed for each IP and user on submission creation. Empty disables the limit"]
+    pub rate_limit_submissions: String,
+
+    #[key = "RATE_LIMIT_COMMENTS"]
+    #[value = "10/60"]
+    #[description = "Requests per seconds allowed for each IP and user on posting and editing comments. Empty disables the limit"]
+    pub rate_limit_comments: String,
 }
 
 // run tests with
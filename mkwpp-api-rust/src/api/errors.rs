@@ -30,6 +30,11 @@ impl FinalErrorResponse {
     fn generate_response(&self) -> HttpResponse {
         HttpResponseBuilder::new(self.status_code).json(self)
     }
+
+    /// For errors that get stored instead of being sent back to a client
+    pub fn messages(&self) -> String {
+        self.non_field_errors.join(": ")
+    }
 }
 
 impl Display for FinalErrorResponse {
@@ -84,6 +89,16 @@ pub enum EveryReturnedError {
     UserOnCooldown,
     NoAssociatedPlayer,
     InvalidChadsoftID,
+    InvalidTwoFactorCode,
+    InvalidTwoFactorChallenge,
+    TwoFactorRequired,
+    UserAlreadyHasPlayer,
+    PlayerAlreadyClaimed,
+    ClaimAlreadyOpen,
+    PasswordBreached,
+    TooManyRequests,
+    LoadingEmailTemplate,
+    SendingWebhook,
 }
 
 impl From<EveryReturnedError> for u64 {
@@ -124,6 +139,16 @@ impl From<EveryReturnedError> for u64 {
             EveryReturnedError::UserOnCooldown => 32,
             EveryReturnedError::NoAssociatedPlayer => 33,
             EveryReturnedError::InvalidChadsoftID => 34,
+            EveryReturnedError::InvalidTwoFactorCode => 35,
+            EveryReturnedError::InvalidTwoFactorChallenge => 36,
+            EveryReturnedError::TwoFactorRequired => 37,
+            EveryReturnedError::UserAlreadyHasPlayer => 38,
+            EveryReturnedError::PlayerAlreadyClaimed => 39,
+            EveryReturnedError::ClaimAlreadyOpen => 40,
+            EveryReturnedError::PasswordBreached => 41,
+            EveryReturnedError::TooManyRequests => 42,
+            EveryReturnedError::LoadingEmailTemplate => 43,
+            EveryReturnedError::SendingWebhook => 44,
         }
     }
 }
@@ -375,6 +400,75 @@ impl EveryReturnedError {
                 vec![String::from("Chadsoft ID is not valid")],
                 HashMap::new(),
             ),
+            Self::InvalidTwoFactorCode => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::BAD_REQUEST,
+                vec![String::from("Two factor authentication code is invalid")],
+                HashMap::new(),
+            ),
+            Self::InvalidTwoFactorChallenge => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::BAD_REQUEST,
+                vec![String::from(
+                    "Two factor authentication challenge is invalid or expired",
+                )],
+                HashMap::new(),
+            ),
+            Self::TwoFactorRequired => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::FORBIDDEN,
+                vec![String::from(
+                    "Two factor authentication is required for this account",
+                )],
+                HashMap::new(),
+            ),
+            Self::UserAlreadyHasPlayer => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::CONFLICT,
+                vec![String::from("This account is already linked to a player")],
+                HashMap::new(),
+            ),
+            Self::PlayerAlreadyClaimed => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::CONFLICT,
+                vec![String::from("This player is already linked to an account")],
+                HashMap::new(),
+            ),
+            Self::ClaimAlreadyOpen => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::CONFLICT,
+                vec![String::from("You already have an open claim request")],
+                HashMap::new(),
+            ),
+            Self::PasswordBreached => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::BAD_REQUEST,
+                vec![String::from("Error validating the password")],
+                std::collections::HashMap::from([(
+                    String::from("password"),
+                    vec![String::from(
+                        "Password has appeared in a data breach, please choose another one",
+                    )],
+                )]),
+            ),
+            Self::TooManyRequests => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::TOO_MANY_REQUESTS,
+                vec![String::from("Too many requests, please try again later")],
+                HashMap::new(),
+            ),
+            Self::LoadingEmailTemplate => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                vec![String::from("Couldn't load the email template")],
+                HashMap::new(),
+            ),
+            Self::SendingWebhook => FinalErrorResponse::new(
+                self.into(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                vec![String::from("There was an error delivering the webhook")],
+                HashMap::new(),
+            ),
         };
 
         let library_error = library_error.to_string();
@@ -0,0 +1,193 @@
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
+    sql::tables::blog_posts::{BlogPostInput, BlogPosts},
+};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
+
+pub fn blog() -> impl HttpServiceFactory {
+    web::scope("/blog")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::BlogAuthor, req, next)
+        }))
+        .route("/list", web::get().to(list))
+        .route("/list", web::post().to(list))
+        .route("/id/{id}", web::get().to(get_by_id))
+        .route("/id/{id}", web::post().to(get_by_id))
+        .route("/revisions/{id}", web::get().to(revisions))
+        .route("/revisions/{id}", web::post().to(revisions))
+        .route("/preview", web::post().to(preview))
+        .route("/create", web::put().to(create))
+        .route("/update", web::put().to(update))
+        .route("/delete", web::put().to(delete))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!(
+    "/list",
+    "/id/:id",
+    "/revisions/:id",
+    "/preview",
+    "/create",
+    "/update",
+    "/delete"
+);
+
+async fn list(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = BlogPosts::get_all_editable(&mut executor).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn get_by_id(
+    _user: AdminUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = BlogPosts::get_editable(&mut executor, path.into_inner()).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn revisions(
+    _user: AdminUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = BlogPosts::get_revisions(&mut executor, path.into_inner()).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+struct PreviewBody {
+    markdown: String,
+}
+
+#[derive(serde::Serialize)]
+struct PreviewResponse {
+    html: String,
+}
+
+/// Renders Markdown the same way saving a post does
+async fn preview(body: web::Json<PreviewBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    send_serialized_data(PreviewResponse {
+        html: crate::markdown::render(&body.markdown),
+    })
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBody {
+    #[serde(flatten)]
+    post: BlogPostInput,
+}
+
+#[derive(serde::Serialize)]
+struct CreateResponse {
+    id: i32,
+}
+
+async fn create(
+    user: AdminUser,
+    body: web::Json<CreateBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut transaction = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state
+            .pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let id = BlogPosts::create(&mut transaction, &body.post, user.user_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    send_serialized_data(CreateResponse { id })
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBody {
+    id: i32,
+    #[serde(flatten)]
+    post: BlogPostInput,
+}
+
+async fn update(
+    user: AdminUser,
+    body: web::Json<UpdateBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut transaction = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state
+            .pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    BlogPosts::update(&mut transaction, body.id, &body.post, user.user_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
+
+#[derive(serde::Deserialize)]
+struct IdBody {
+    id: i32,
+}
+
+async fn delete(body: web::Json<IdBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    BlogPosts::delete(&mut executor, body.id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
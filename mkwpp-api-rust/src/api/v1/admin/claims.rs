@@ -0,0 +1,78 @@
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
+    sql::tables::{players::claims::PlayerClaims, submissions::SubmissionStatus},
+};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
+
+pub fn claims() -> impl HttpServiceFactory {
+    web::scope("/claims")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::PlayerEditor, req, next)
+        }))
+        .route("/list", web::get().to(list))
+        .route("/list", web::post().to(list))
+        .route("/review", web::put().to(review))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/list", "/review");
+
+async fn list(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = PlayerClaims::get_open(&mut executor).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewBody {
+    claim_id: i32,
+    status: SubmissionStatus,
+    reviewer_note: Option<String>,
+}
+
+async fn review(
+    user: AdminUser,
+    body: web::Json<ReviewBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    PlayerClaims::review(
+        &mut transaction,
+        body.claim_id,
+        user.user_id,
+        body.status,
+        body.reviewer_note,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
@@ -0,0 +1,94 @@
+use crate::{
+    api::{
+        errors::FinalErrorResponse,
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
+    sql::tables::comments::Comments,
+};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
+
+pub fn comments() -> impl HttpServiceFactory {
+    web::scope("/comments")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::CommentModerator, req, next)
+        }))
+        .route("/list", web::get().to(list))
+        .route("/list", web::post().to(list))
+        .route("/remove", web::put().to(remove))
+        .route("/restore", web::put().to(restore))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/list", "/remove", "/restore");
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListQuery {
+    #[serde(default)]
+    only_removed: bool,
+}
+
+async fn list(
+    _user: AdminUser,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = Comments::get_recent_for_moderation(&mut executor, query.only_removed).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoveBody {
+    id: i32,
+    reason: Option<String>,
+}
+
+async fn remove(
+    user: AdminUser,
+    body: web::Json<RemoveBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    Comments::remove(&mut executor, body.id, user.user_id, body.reason.as_deref()).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
+
+#[derive(serde::Deserialize)]
+struct IdBody {
+    id: i32,
+}
+
+async fn restore(body: web::Json<IdBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    Comments::restore(&mut executor, body.id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
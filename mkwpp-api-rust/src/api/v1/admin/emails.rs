@@ -0,0 +1,58 @@
+use crate::{
+    api::{
+        errors::FinalErrorResponse,
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
+    mail::outbox::OutboxEmail,
+};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
+
+pub fn emails() -> impl HttpServiceFactory {
+    web::scope("/emails")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::Superuser, req, next)
+        }))
+        .route("/status", web::get().to(status))
+        .route("/status", web::post().to(status))
+        .route("/retry", web::put().to(retry))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/status", "/retry");
+
+async fn status(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = OutboxEmail::get_status(&mut executor).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryBody {
+    id: i32,
+}
+
+async fn retry(body: web::Json<RetryBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    OutboxEmail::retry(&mut executor, body.id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
@@ -1,27 +1,59 @@
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{
+    HttpMessage, HttpResponse,
+    body::BoxBody,
+    dev::{HttpServiceFactory, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
 
 use crate::{
-    api::{errors::FinalErrorResponse, v1::close_connection},
-    auth::is_user_admin,
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::close_connection,
+    },
+    auth::{
+        extractor::{AdminUser, AuthenticatedUser, get_session_token},
+        roles::Role,
+    },
 };
 
+mod blog;
+mod claims;
+mod comments;
+mod emails;
 mod players;
 mod regions;
 mod scores;
 mod submissions;
 mod users;
+mod webhooks;
 
 pub fn admin() -> impl HttpServiceFactory {
     web::scope("/admin")
+        .route("/is_admin", web::get().to(is_admin_from_request))
         .route("/is_admin", web::post().to(is_admin))
         .service(regions::regions())
         .service(players::players())
         .service(scores::scores())
         .service(users::users())
         .service(submissions::submissions())
+        .service(claims::claims())
+        .service(emails::emails())
+        .service(webhooks::webhooks())
+        .service(blog::blog())
+        .service(comments::comments())
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/is_admin", "/players", "/regions");
+default_paths_fn!(
+    "/is_admin",
+    "/players",
+    "/regions",
+    "/claims",
+    "/emails",
+    "/webhooks",
+    "/blog",
+    "/comments"
+);
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,7 +61,63 @@ pub struct UserDataBody {
     pub session_token: String,
 }
 
+/// Guard for admin scopes, used through `middleware::from_fn`.
+/// The session token is read from the header/cookie, falling back to the
+/// `sessionToken` field of the JSON body, which is put back for the handler
+pub async fn require_role(
+    role: Role,
+    mut req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let session_token = match get_session_token(req.request()) {
+        Some(v) => v,
+        None => {
+            let body = req.extract::<web::Bytes>().await?;
+            req.set_payload(body.clone().into());
+            serde_json::from_slice::<UserDataBody>(&body)
+                .map_err(|e| EveryReturnedError::InvalidSessionToken.into_final_error(e))?
+                .session_token
+        }
+    };
+
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let user = AuthenticatedUser::from_session_token(&session_token, &mut connection).await?;
+
+    close_connection(connection).await?;
+
+    if !user.has_role(role) {
+        return Err(EveryReturnedError::InsufficientPermissions
+            .into_final_error("")
+            .into());
+    }
+
+    req.extensions_mut().insert(AdminUser { user, role });
+
+    next.call(req).await
+}
+
+#[derive(serde::Deserialize)]
+struct IsAdminQuery {
+    /// Without a role, any staff role counts
+    role: Option<Role>,
+}
+
+impl IsAdminQuery {
+    fn is_granted_to(&self, user: &AuthenticatedUser) -> bool {
+        match self.role {
+            None => !user.roles.is_empty(),
+            Some(role) => user.has_role(role),
+        }
+    }
+}
+
 async fn is_admin(
+    query: web::Query<IsAdminQuery>,
     body: web::Json<UserDataBody>,
 ) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
@@ -40,17 +128,20 @@ async fn is_admin(
         data.acquire_pg_connection().await?
     };
 
-    let is_admin = is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?;
+    let user = AuthenticatedUser::from_session_token(&body.session_token, &mut connection).await?;
 
     close_connection(connection).await?;
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
-        .body(format!(r#"{{"isAdmin":{is_admin}}}"#)))
+        .body(format!(r#"{{"isAdmin":{}}}"#, query.is_granted_to(&user))))
+}
+
+async fn is_admin_from_request(
+    query: web::Query<IsAdminQuery>,
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(format!(r#"{{"isAdmin":{}}}"#, query.is_granted_to(&user))))
 }
@@ -1,47 +1,33 @@
 use crate::{
     api::{
         errors::{EveryReturnedError, FinalErrorResponse},
-        v1::close_connection,
+        v1::{close_connection, send_serialized_data},
     },
-    auth::is_user_admin,
+    auth::{extractor::AdminUser, roles::Role},
     custom_serde::DateAsTimestampNumber,
     sql::tables::players::Players,
 };
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
 
 pub fn players() -> impl HttpServiceFactory {
     web::scope("/players")
-        .route("/list", web::post().to(list))
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::PlayerEditor, req, next)
+        }))
+        .route("/list", web::get().to(get_list))
+        .route("/list", web::post().to(get_list))
         .route("/insert", web::put().to(insert_or_edit))
         .route("/edit", web::patch().to(insert_or_edit))
+        .route("/merge", web::post().to(merge))
         .route(
             "/delete",
             web::delete().to(crate::api::v1::delete_by_id::<Players>),
         )
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/list", "/insert", "/edit", "/delete");
-
-async fn list(body: web::Json<super::UserDataBody>) -> Result<HttpResponse, FinalErrorResponse> {
-    let body = body.into_inner();
-
-    let data = crate::app_state::access_app_state().await;
-    let mut connection = {
-        let data = data.read().await;
-        data.acquire_pg_connection().await?
-    };
-
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
+default_paths_fn!("/list", "/insert", "/edit", "/merge", "/delete");
 
+async fn get_list(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
     crate::api::v1::get_star_query::<Players>().await
 }
 
@@ -60,10 +46,10 @@ struct InsertOrEditBody {
     last_activity: chrono::NaiveDate,
     submitters: Vec<i32>,
     chadsoft_ids: Vec<String>,
-    session_token: String,
 }
 
 async fn insert_or_edit(
+    _user: AdminUser,
     body: web::Json<InsertOrEditBody>,
 ) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
@@ -74,17 +60,6 @@ async fn insert_or_edit(
         data.acquire_pg_connection().await?
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
     let chadsoft_ids = body
         .chadsoft_ids
         .iter()
@@ -116,3 +91,35 @@ async fn insert_or_edit(
         .content_type("application/json")
         .body(r#"{"success":true}"#))
 }
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeBody {
+    from_id: i32,
+    into_id: i32,
+}
+
+async fn merge(
+    _user: AdminUser,
+    body: web::Json<MergeBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let summary = Players::merge(&mut transaction, body.from_id, body.into_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    send_serialized_data(summary)
+}
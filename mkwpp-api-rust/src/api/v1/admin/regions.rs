@@ -1,25 +1,29 @@
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
 
 use crate::{
     api::{
         errors::{EveryReturnedError, FinalErrorResponse},
         v1::close_connection,
     },
-    auth::is_user_admin,
+    auth::{extractor::AdminUser, roles::Role},
     sql::tables::regions::{RegionType, Regions},
 };
 
 pub fn regions() -> impl HttpServiceFactory {
     web::scope("/regions")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::PlayerEditor, req, next)
+        }))
         .route("/insert", web::put().to(insert_or_edit))
         .route("/edit", web::patch().to(insert_or_edit))
         .route(
             "/delete",
             web::delete().to(crate::api::v1::delete_by_id::<Regions>),
         )
+        .route("/group_members", web::put().to(set_group_members))
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/insert", "/edit", "/delete");
+default_paths_fn!("/insert", "/edit", "/delete", "/group_members");
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,10 +33,10 @@ struct InsertOrEditBody {
     region_type: RegionType,
     parent_id: Option<i32>,
     is_ranked: bool,
-    session_token: String,
 }
 
 async fn insert_or_edit(
+    _user: AdminUser,
     body: web::Json<InsertOrEditBody>,
 ) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
@@ -43,17 +47,6 @@ async fn insert_or_edit(
         data.acquire_pg_connection().await?
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
     Regions::insert_or_edit(
         &mut connection,
         body.id,
@@ -70,3 +63,35 @@ async fn insert_or_edit(
         .content_type("application/json")
         .body(r#"{"success":true}"#))
 }
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupMembersBody {
+    group_id: i32,
+    region_ids: Vec<i32>,
+}
+
+async fn set_group_members(
+    _user: AdminUser,
+    body: web::Json<GroupMembersBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    Regions::set_group_members(&mut transaction, body.group_id, &body.region_ids).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
@@ -3,14 +3,18 @@ use crate::{
         errors::{EveryReturnedError, FinalErrorResponse},
         v1::{DeleteBody, close_connection, decode_row_to_table, send_serialized_data},
     },
-    auth::is_user_admin,
+    auth::{extractor::AdminUser, roles::Role},
     custom_serde::DateAsTimestampNumber,
     sql::tables::{Category, scores::Scores},
 };
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
 
 pub fn scores() -> impl HttpServiceFactory {
     web::scope("/scores")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::ScoreEditor, req, next)
+        }))
+        .route("/list", web::get().to(get_list))
         .route("/list", web::post().to(list))
         .route("/id", web::post().to(get_by_id))
         .route("/insert", web::put().to(insert_or_edit))
@@ -26,34 +30,33 @@ default_paths_fn!("/list", "/id", "/insert", "/edit", "/delete");
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ListBody {
-    pub session_token: String,
     pub track_id: i32,
 }
 
-async fn list(body: web::Json<ListBody>) -> Result<HttpResponse, FinalErrorResponse> {
+async fn list(
+    _user: AdminUser,
+    body: web::Json<ListBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
 
-    let data = crate::app_state::access_app_state().await;
-    let mut connection = {
-        let data = data.read().await;
-        data.acquire_pg_connection().await?
-    };
-
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
     crate::api::v1::basic_get::<Scores>(async |x| Scores::filter_by_track(body.track_id, x).await)
         .await
 }
 
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListQuery {
+    pub track_id: i32,
+}
+
+async fn get_list(
+    _user: AdminUser,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let track_id = query.into_inner().track_id;
+    crate::api::v1::basic_get::<Scores>(async |x| Scores::filter_by_track(track_id, x).await).await
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct InsertOrEditBody {
@@ -69,32 +72,27 @@ struct InsertOrEditBody {
     ghost_link: Option<String>,
     comment: Option<String>,
     admin_note: Option<String>,
-    session_token: String,
 }
 
 async fn insert_or_edit(
+    _user: AdminUser,
     body: web::Json<InsertOrEditBody>,
 ) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
 
     let data = crate::app_state::access_app_state().await;
-    let mut connection = {
+    let (live_feed_open, mut transaction) = {
         let data = data.read().await;
-        data.acquire_pg_connection().await?
+        (
+            data.live_feed.has_subscribers(),
+            data.pg_pool
+                .begin()
+                .await
+                .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?,
+        )
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
-    Scores::insert_or_edit(
+    let (_, events) = Scores::insert_or_edit(
         body.id,
         body.value,
         body.category,
@@ -106,18 +104,27 @@ async fn insert_or_edit(
         body.ghost_link,
         body.comment,
         body.admin_note,
-        &mut connection,
+        live_feed_open,
+        &mut transaction,
     )
     .await?;
 
-    close_connection(connection).await?;
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    data.read().await.live_feed.publish(events);
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .body(r#"{"success":true}"#))
 }
 
-async fn get_by_id(body: web::Json<DeleteBody>) -> Result<HttpResponse, FinalErrorResponse> {
+async fn get_by_id(
+    _user: AdminUser,
+    body: web::Json<DeleteBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
 
     let data = crate::app_state::access_app_state().await;
@@ -126,17 +133,6 @@ async fn get_by_id(body: web::Json<DeleteBody>) -> Result<HttpResponse, FinalErr
         data.acquire_pg_connection().await?
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
     let data = Scores::get_from_id(body.id, &mut connection).await?;
 
     close_connection(connection).await?;
@@ -4,17 +4,23 @@ use crate::{
         v1::{decode_rows_to_table, send_serialized_data},
     },
     app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
     sql::tables::{
         BasicTableQueries,
         players::Players,
         submissions::{Submissions, edit_submissions::EditSubmissions},
     },
 };
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
 
 pub fn submissions() -> impl HttpServiceFactory {
     web::scope("/submissions")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::SubmissionReviewer, req, next)
+        }))
+        .route("/list_submissions", web::get().to(get))
         .route("/list_submissions", web::post().to(get))
+        .route("/list_edit_submissions", web::get().to(get_edit))
         .route("/list_edit_submissions", web::post().to(get_edit))
         .route(
             "/delete_submission",
@@ -35,7 +41,7 @@ default_paths_fn!(
     "/delete_edit_submission"
 );
 
-async fn get() -> Result<HttpResponse, FinalErrorResponse> {
+async fn get(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
     let mut executor = {
         let app_state = access_app_state().await;
         let app_state = app_state.read().await;
@@ -53,7 +59,7 @@ async fn get() -> Result<HttpResponse, FinalErrorResponse> {
     send_serialized_data(data)
 }
 
-async fn get_edit() -> Result<HttpResponse, FinalErrorResponse> {
+async fn get_edit(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
     let mut executor = {
         let app_state = access_app_state().await;
         let app_state = app_state.read().await;
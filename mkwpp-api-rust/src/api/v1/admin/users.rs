@@ -1,18 +1,26 @@
 use crate::{
     api::{
         errors::{EveryReturnedError, FinalErrorResponse},
-        v1::close_connection,
+        v1::{close_connection, send_serialized_data},
     },
     auth::{
-        Users, is_user_admin,
+        Users,
+        extractor::AdminUser,
+        roles::Role,
         validated_strings::{self, ValidatedString},
     },
 };
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
 
 pub fn users() -> impl HttpServiceFactory {
     web::scope("/users")
-        .route("/list", web::post().to(list))
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::Superuser, req, next)
+        }))
+        .route("/list", web::get().to(get_list))
+        .route("/list", web::post().to(get_list))
+        .route("/roles/{user_id}", web::get().to(get_roles))
+        .route("/roles", web::put().to(set_roles))
         .route("/insert", web::put().to(insert_or_edit))
         .route("/edit", web::patch().to(insert_or_edit))
         .route(
@@ -21,28 +29,16 @@ pub fn users() -> impl HttpServiceFactory {
         )
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/list", "/insert", "/edit", "/delete");
-
-async fn list(body: web::Json<super::UserDataBody>) -> Result<HttpResponse, FinalErrorResponse> {
-    let body = body.into_inner();
-
-    let data = crate::app_state::access_app_state().await;
-    let mut connection = {
-        let data = data.read().await;
-        data.acquire_pg_connection().await?
-    };
-
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
+default_paths_fn!(
+    "/list",
+    "/roles/:userId",
+    "/roles",
+    "/insert",
+    "/edit",
+    "/delete"
+);
 
+async fn get_list(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
     crate::api::v1::get_star_query::<Users>().await
 }
 
@@ -57,10 +53,10 @@ struct InsertOrEditBody {
     is_active: bool,
     is_verified: bool,
     player_id: Option<i32>,
-    session_token: String,
 }
 
 async fn insert_or_edit(
+    _user: AdminUser,
     body: web::Json<InsertOrEditBody>,
 ) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
@@ -71,17 +67,6 @@ async fn insert_or_edit(
         data.acquire_pg_connection().await?
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
     let username = validated_strings::username::Username::new_from_string(body.username)?;
     let email = validated_strings::email::Email::new_from_string(body.email)?;
     let password = match body.password.is_empty() {
@@ -111,3 +96,54 @@ async fn insert_or_edit(
         .content_type("application/json")
         .body(r#"{"success":true}"#))
 }
+
+async fn get_roles(
+    _user: AdminUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let roles = Role::get_user_roles(path.into_inner(), &mut connection).await?;
+
+    close_connection(connection).await?;
+
+    send_serialized_data(roles)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetRolesBody {
+    user_id: i32,
+    roles: Vec<Role>,
+}
+
+async fn set_roles(
+    _user: AdminUser,
+    body: web::Json<SetRolesBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    Role::set_user_roles(body.user_id, &body.roles, &mut transaction).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
@@ -0,0 +1,176 @@
+use crate::{
+    api::{
+        errors::FinalErrorResponse,
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{extractor::AdminUser, roles::Role},
+    webhooks::{WebhookDeliveries, WebhookEvent, WebhookFormat, Webhooks},
+};
+use actix_web::{HttpResponse, dev::HttpServiceFactory, middleware, web};
+
+pub fn webhooks() -> impl HttpServiceFactory {
+    web::scope("/webhooks")
+        .wrap(middleware::from_fn(|req, next| {
+            super::require_role(Role::Superuser, req, next)
+        }))
+        .route("/list", web::get().to(list))
+        .route("/list", web::post().to(list))
+        .route("/create", web::put().to(create))
+        .route("/update", web::put().to(update))
+        .route("/delete", web::put().to(delete))
+        .route("/deliveries", web::get().to(deliveries))
+        .route("/deliveries", web::post().to(deliveries))
+        .route("/retry", web::put().to(retry))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!(
+    "/list",
+    "/create",
+    "/update",
+    "/delete",
+    "/deliveries",
+    "/retry"
+);
+
+async fn list(_user: AdminUser) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = Webhooks::get_all(&mut executor).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBody {
+    url: String,
+    events: Vec<WebhookEvent>,
+    #[serde(default)]
+    format: WebhookFormat,
+}
+
+async fn create(
+    user: AdminUser,
+    body: web::Json<CreateBody>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = Webhooks::create(
+        &mut executor,
+        &body.url,
+        &body.events,
+        body.format,
+        user.user_id,
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBody {
+    id: i32,
+    url: String,
+    events: Vec<WebhookEvent>,
+    format: Option<WebhookFormat>,
+    is_active: bool,
+}
+
+async fn update(body: web::Json<UpdateBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    Webhooks::update(
+        &mut executor,
+        body.id,
+        &body.url,
+        &body.events,
+        body.format,
+        body.is_active,
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IdBody {
+    id: i32,
+}
+
+async fn delete(body: web::Json<IdBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    Webhooks::delete(&mut executor, body.id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveriesQuery {
+    webhook_id: Option<i32>,
+}
+
+async fn deliveries(
+    _user: AdminUser,
+    query: web::Query<DeliveriesQuery>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = WebhookDeliveries::get_recent(&mut executor, query.webhook_id).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn retry(body: web::Json<IdBody>) -> Result<HttpResponse, FinalErrorResponse> {
+    let mut executor = {
+        let app_state = access_app_state().await;
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    WebhookDeliveries::retry(&mut executor, body.id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"success":true}"#))
+}
@@ -0,0 +1,155 @@
+use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{BareMinimumValidationData, extractor::AuthenticatedUser, is_valid_token},
+    custom_serde::ChadsoftIDConversion,
+    sql::tables::players::claims::PlayerClaims,
+};
+
+pub fn claims() -> impl HttpServiceFactory {
+    web::scope("/claims")
+        .route("/create", web::put().to(create))
+        .route("/list", web::get().to(list_from_request))
+        .route("/list", web::post().to(list))
+        .route("/cancel", web::put().to(cancel))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/create", "/list", "/cancel");
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBody {
+    player_id: i32,
+    #[serde(
+        default,
+        deserialize_with = "ChadsoftIDConversion::deserialize_from_string"
+    )]
+    chadsoft_id: Option<i64>,
+    user_note: Option<String>,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn create(
+    body: web::Json<CreateBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    PlayerClaims::create(
+        &mut transaction,
+        body.validation_data.user_id,
+        body.player_id,
+        body.chadsoft_id,
+        body.user_note,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
+async fn list(
+    body: web::Json<BareMinimumValidationData>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(&body.session_token, body.user_id, &mut executor).await? {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let data = PlayerClaims::get_by_user_id(&mut executor, body.user_id).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn list_from_request(
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = PlayerClaims::get_by_user_id(&mut executor, user.user_id).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelBody {
+    claim_id: i32,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn cancel(
+    body: web::Json<CancelBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    PlayerClaims::cancel(&mut executor, body.validation_data.user_id, body.claim_id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
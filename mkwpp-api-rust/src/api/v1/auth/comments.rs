@@ -0,0 +1,166 @@
+use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, rate_limit::rate_limited, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{BareMinimumValidationData, is_valid_token},
+    sql::tables::comments::{CommentTarget, Comments},
+};
+
+pub fn comments() -> impl HttpServiceFactory {
+    web::scope("/comments")
+        .route(
+            "/create",
+            rate_limited(
+                web::post().to(create),
+                "create_comment",
+                &crate::ENV_VARS.rate_limit_comments,
+            ),
+        )
+        .route(
+            "/edit",
+            rate_limited(
+                web::post().to(edit),
+                "edit_comment",
+                &crate::ENV_VARS.rate_limit_comments,
+            ),
+        )
+        .route("/delete", web::post().to(delete))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/create", "/edit", "/delete");
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBody {
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+    #[serde(flatten)]
+    target: CommentTarget,
+    parent_id: Option<i32>,
+    markdown: String,
+}
+
+#[derive(serde::Serialize)]
+struct CreateResponse {
+    id: i32,
+}
+
+async fn create(
+    body: web::Json<CreateBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let id = Comments::create(
+        &mut executor,
+        body.target,
+        body.parent_id,
+        body.validation_data.user_id,
+        &body.markdown,
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(CreateResponse { id })
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditBody {
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+    id: i32,
+    markdown: String,
+}
+
+async fn edit(body: web::Json<EditBody>) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    Comments::edit(
+        &mut executor,
+        body.id,
+        body.validation_data.user_id,
+        &body.markdown,
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteBody {
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+    id: i32,
+}
+
+async fn delete(
+    body: web::Json<DeleteBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    Comments::delete(&mut executor, body.id, body.validation_data.user_id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
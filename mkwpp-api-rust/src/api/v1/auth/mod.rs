@@ -3,33 +3,90 @@ use actix_web::{HttpRequest, HttpResponse, dev::HttpServiceFactory, web};
 use crate::{
     api::{
         errors::{EveryReturnedError, FinalErrorResponse},
-        v1::send_serialized_data,
+        v1::{rate_limit::rate_limited, send_serialized_data},
     },
     auth::{
-        BareMinimumValidationData, activate_account, is_valid_token,
+        BareMinimumValidationData, activate_account,
+        extractor::{AuthenticatedUser, removal_session_cookie, session_cookie},
+        is_valid_token,
+        two_factor::LogInResult,
         validated_strings::ValidatedString,
     },
 };
 
+mod claims;
+mod comments;
+mod notifications;
 mod player;
+mod sessions;
 pub mod submissions;
+mod two_factor;
 
 pub fn auth() -> impl HttpServiceFactory {
     web::scope("/auth")
-        .route("/register", web::put().to(register))
-        .route("/login", web::put().to(login))
+        .route(
+            "/register",
+            rate_limited(
+                web::put().to(register),
+                "register",
+                &crate::ENV_VARS.rate_limit_register,
+            ),
+        )
+        .route(
+            "/login",
+            rate_limited(
+                web::put().to(login),
+                "login",
+                &crate::ENV_VARS.rate_limit_login,
+            ),
+        )
         .route("/logout", web::put().to(logout))
+        .route("/logout", web::delete().to(logout_from_request))
         .route("/activate", web::put().to(activate))
+        .route(
+            "/resend_activation",
+            rate_limited(
+                web::put().to(resend_activation),
+                "resend_activation",
+                &crate::ENV_VARS.rate_limit_email,
+            ),
+        )
         .route("/user_data", web::post().to(user_data))
-        .route("/password_forgot", web::put().to(password_forgot))
+        .route("/me", web::get().to(me))
+        .route(
+            "/password_forgot",
+            rate_limited(
+                web::put().to(password_forgot),
+                "password_forgot",
+                &crate::ENV_VARS.rate_limit_email,
+            ),
+        )
         .route("/password_reset", web::put().to(password_reset))
         .route(
             "/password_reset_check_token",
             web::post().to(password_reset_check_token),
         )
         .route("/update_password", web::put().to(update_password))
+        .route(
+            "/change_email",
+            rate_limited(
+                web::put().to(change_email),
+                "change_email",
+                &crate::ENV_VARS.rate_limit_email,
+            ),
+        )
+        .route("/change_email_confirm", web::put().to(change_email_confirm))
+        .route("/locale", web::put().to(set_locale))
+        .route("/export", web::get().to(export_from_request))
+        .route("/export", web::post().to(export))
+        .route("/delete_account", web::put().to(delete_account))
         .service(player::player())
+        .service(claims::claims())
+        .service(comments::comments())
+        .service(notifications::notifications())
+        .service(sessions::sessions())
         .service(submissions::submissions())
+        .service(two_factor::two_factor())
         .default_service(web::get().to(default))
 }
 default_paths_fn!(
@@ -37,17 +94,46 @@ default_paths_fn!(
     "/logout",
     "/login",
     "/user_data",
+    "/me",
     "/update_password",
+    "/change_email",
+    "/change_email_confirm",
+    "/locale",
+    "/export",
+    "/delete_account",
     "/player",
-    "/submissions"
+    "/claims",
+    "/comments",
+    "/notifications",
+    "/sessions",
+    "/submissions",
+    "/2fa"
 );
 
+fn get_user_agent(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|x| x.to_str().ok())
+        .map(String::from)
+}
+
+fn with_cookie(
+    mut response: HttpResponse,
+    cookie: actix_web::cookie::Cookie<'static>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    response
+        .add_cookie(&cookie)
+        .map_err(|e| EveryReturnedError::SerializingDataToJSON.into_final_error(e))?;
+    Ok(response)
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RegisterBody {
     username: String,
     password: String,
     email: String,
+    locale: Option<String>,
 }
 
 async fn register(
@@ -70,7 +156,13 @@ async fn register(
         crate::auth::validated_strings::password::Password::new_from_string(body.password)?;
     let email = crate::auth::validated_strings::email::Email::new_from_string(body.email)?;
 
-    crate::auth::register(username, password, email, &mut transaction).await?;
+    let locale = match &body.locale {
+        Some(v) if crate::mail::templates::is_valid_locale(v) => v.as_str(),
+        Some(_) => return Err(EveryReturnedError::InvalidInput.into_final_error("Invalid locale")),
+        None => crate::ENV_VARS.email_default_locale.as_str(),
+    };
+
+    crate::auth::register(username, password, email, locale, &mut transaction).await?;
     transaction
         .commit()
         .await
@@ -109,16 +201,29 @@ async fn login(
     let username =
         crate::auth::validated_strings::username::Username::new_from_string(body.username)?;
     let password =
-        crate::auth::validated_strings::password::Password::new_from_string(body.password)?;
+        crate::auth::validated_strings::password::Password::new_for_verification(body.password)?;
 
-    let login_attempt = crate::auth::login(username, password, ip, &mut transaction).await?;
+    let login_attempt = crate::auth::login(
+        username,
+        password,
+        ip,
+        get_user_agent(&req),
+        &mut transaction,
+    )
+    .await?;
 
     transaction
         .commit()
         .await
         .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
 
-    send_serialized_data(login_attempt)
+    match &login_attempt {
+        LogInResult::Session(session) => {
+            let cookie = session_cookie(session);
+            with_cookie(send_serialized_data(login_attempt)?, cookie)
+        }
+        LogInResult::Challenge(_) => send_serialized_data(login_attempt),
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -138,7 +243,9 @@ async fn user_data(
         data.acquire_pg_connection().await?
     };
 
-    send_serialized_data(crate::auth::get_user_data(&body.session_token, &mut connection).await?)
+    let user_data = crate::auth::get_user_data(&body.session_token, &mut connection).await?;
+
+    send_serialized_data(user_data)
 }
 
 async fn logout(
@@ -162,9 +269,51 @@ async fn logout(
         .await
         .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
 
-    Ok(HttpResponse::Ok()
-        .content_type("application/json")
-        .body("{}"))
+    with_cookie(
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .body("{}"),
+        removal_session_cookie(),
+    )
+}
+
+async fn logout_from_request(
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    crate::auth::logout(&user.session_token, &mut connection).await?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    with_cookie(
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .body("{}"),
+        removal_session_cookie(),
+    )
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MeResponse {
+    user_id: i32,
+    player_id: Option<i32>,
+    username: String,
+    roles: Vec<crate::auth::roles::Role>,
+}
+
+async fn me(user: AuthenticatedUser) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    send_serialized_data(MeResponse {
+        user_id: user.user_id,
+        player_id: user.player_id,
+        username: user.username,
+        roles: user.roles,
+    })
 }
 
 #[derive(serde::Deserialize)]
@@ -199,8 +348,9 @@ async fn update_password(
         return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
     }
 
-    let old_password =
-        crate::auth::validated_strings::password::Password::new_from_string(body.old_password)?;
+    let old_password = crate::auth::validated_strings::password::Password::new_for_verification(
+        body.old_password,
+    )?;
     let new_password =
         crate::auth::validated_strings::password::Password::new_from_string(body.new_password)?;
 
@@ -222,6 +372,91 @@ async fn update_password(
         .body("{}"))
 }
 
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeEmailBody {
+    password: String,
+    new_email: String,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn change_email(
+    req: HttpRequest,
+    body: web::Json<ChangeEmailBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let body = body.into_inner();
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let password =
+        crate::auth::validated_strings::password::Password::new_for_verification(body.password)?;
+    let new_email = crate::auth::validated_strings::email::Email::new_from_string(body.new_email)?;
+
+    let result = crate::auth::change_email_token_gen(
+        body.validation_data.user_id,
+        password,
+        new_email,
+        req.peer_addr().unwrap().ip(),
+        &mut transaction,
+    )
+    .await;
+
+    // Failed attempts have to be stored for the cooldown
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    result?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
+async fn change_email_confirm(
+    body: web::Json<TokenCheck>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    crate::auth::change_email(&body.token, &mut transaction).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
 #[derive(serde::Deserialize)]
 struct ActivateBody {
     token: String,
@@ -256,6 +491,33 @@ struct PasswordForgotBody {
     email: String,
 }
 
+async fn resend_activation(
+    body: web::Json<PasswordForgotBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let email = crate::auth::validated_strings::email::Email::new_from_string(body.email)?;
+
+    crate::auth::resend_activation(email, &mut transaction).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
 async fn password_forgot(
     body: web::Json<PasswordForgotBody>,
 ) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
@@ -344,3 +606,137 @@ async fn password_reset_check_token(
         .content_type("application/json")
         .body(r#"{"is_valid":true}"#))
 }
+
+async fn export(
+    body: web::Json<BareMinimumValidationData>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(&body.session_token, body.user_id, &mut connection).await? {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let data =
+        crate::auth::personal_data::export(body.user_id, &body.session_token, &mut connection)
+            .await?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    send_serialized_data(data)
+}
+
+async fn export_from_request(
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let data =
+        crate::auth::personal_data::export(user.user_id, &user.session_token, &mut connection)
+            .await?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteAccountBody {
+    password: String,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn delete_account(
+    body: web::Json<DeleteAccountBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let body = body.into_inner();
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let password =
+        crate::auth::validated_strings::password::Password::new_for_verification(body.password)?;
+
+    crate::auth::personal_data::delete_account(
+        body.validation_data.user_id,
+        password,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    with_cookie(
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .body("{}"),
+        removal_session_cookie(),
+    )
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetLocaleBody {
+    locale: String,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn set_locale(
+    body: web::Json<SetLocaleBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut connection,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    crate::auth::set_locale(body.validation_data.user_id, &body.locale, &mut connection).await?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
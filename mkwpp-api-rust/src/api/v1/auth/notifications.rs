@@ -0,0 +1,99 @@
+use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{BareMinimumValidationData, extractor::AuthenticatedUser, is_valid_token},
+    mail::notifications::NotificationPreferences,
+};
+
+pub fn notifications() -> impl HttpServiceFactory {
+    web::scope("/notifications")
+        .route("/preferences", web::get().to(preferences_from_request))
+        .route("/preferences", web::post().to(preferences))
+        .route("/update", web::put().to(update))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/preferences", "/update");
+
+async fn preferences(
+    body: web::Json<BareMinimumValidationData>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(&body.session_token, body.user_id, &mut executor).await? {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let data = NotificationPreferences::get(&mut executor, body.user_id).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn preferences_from_request(
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = NotificationPreferences::get(&mut executor, user.user_id).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBody {
+    #[serde(flatten)]
+    preferences: NotificationPreferences,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn update(
+    body: web::Json<UpdateBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    body.preferences
+        .set(&mut executor, body.validation_data.user_id)
+        .await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
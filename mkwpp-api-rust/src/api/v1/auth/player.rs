@@ -7,8 +7,8 @@ use crate::{
     },
     app_state::access_app_state,
     auth::{
-        BareMinimumValidationData, get_user_data, get_user_id_from_player_id, is_user_admin,
-        is_valid_token,
+        BareMinimumValidationData, get_user_data, get_user_id_from_player_id, is_valid_token,
+        roles::Role, user_has_role,
     },
     sql::tables::players::Players,
 };
@@ -147,7 +147,7 @@ async fn get_submittees(
         return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
     }
 
-    let data = match is_user_admin(data.user_id, &mut executor).await? {
+    let data = match user_has_role(data.user_id, Role::SubmissionReviewer, &mut executor).await? {
         true => Players::get_ids_but_list(&mut executor, &[]).await?,
         false => Players::get_submittees(&mut executor, data.user_id).await?,
     };
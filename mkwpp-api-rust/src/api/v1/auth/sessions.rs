@@ -0,0 +1,170 @@
+use actix_web::{HttpRequest, HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, send_serialized_data},
+    },
+    app_state::access_app_state,
+    auth::{
+        BareMinimumValidationData, extractor::AuthenticatedUser, is_valid_token, sessions::Sessions,
+    },
+};
+
+pub fn sessions() -> impl HttpServiceFactory {
+    web::scope("/sessions")
+        .route("/list", web::get().to(list_from_request))
+        .route("/list", web::post().to(list))
+        .route("/revoke", web::put().to(revoke))
+        .route("/revoke_all", web::put().to(revoke_all))
+        .route("/rotate", web::put().to(rotate))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/list", "/revoke", "/revoke_all", "/rotate");
+
+async fn list(
+    body: web::Json<BareMinimumValidationData>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(&body.session_token, body.user_id, &mut executor).await? {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    let data = Sessions::get_by_user_id(&mut executor, body.user_id, &body.session_token).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn list_from_request(
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = Sessions::get_by_user_id(&mut executor, user.user_id, &user.session_token).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeBody {
+    session_id: i32,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn revoke(
+    body: web::Json<RevokeBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    Sessions::revoke(&mut executor, body.validation_data.user_id, body.session_id).await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeAllBody {
+    #[serde(default)]
+    keep_current: bool,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn revoke_all(
+    body: web::Json<RevokeAllBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut executor,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    Sessions::revoke_all(
+        &mut executor,
+        body.validation_data.user_id,
+        body.keep_current
+            .then_some(body.validation_data.session_token.as_str()),
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
+
+async fn rotate(
+    req: HttpRequest,
+    body: web::Json<super::UserDataBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let app_state = access_app_state().await;
+    let mut executor = {
+        let app_state = app_state.read().await;
+        app_state.acquire_pg_connection().await?
+    };
+
+    let data = Sessions::rotate(
+        &mut executor,
+        &body.session_token,
+        req.peer_addr().unwrap().ip(),
+        super::get_user_agent(&req),
+    )
+    .await?;
+
+    close_connection(executor).await?;
+
+    let cookie = crate::auth::extractor::session_cookie(&data);
+    super::with_cookie(send_serialized_data(data)?, cookie)
+}
@@ -7,15 +7,22 @@ use sqlx::{
 use crate::{
     api::{
         errors::{EveryReturnedError, FinalErrorResponse},
-        v1::{close_connection, decode_row_to_table, decode_rows_to_table, send_serialized_data},
+        v1::{
+            close_connection, decode_row_to_table, decode_rows_to_table,
+            rate_limit::rate_limited_unless, send_serialized_data,
+        },
     },
-    app_state::access_app_state,
-    auth::{BareMinimumValidationData, get_user_data, is_user_admin, is_valid_token},
+    app_state::{
+        access_app_state,
+        live::{LiveEvent, LiveEventKind},
+    },
+    auth::{BareMinimumValidationData, get_user_data, is_valid_token, roles::Role, user_has_role},
     custom_serde::DateAsTimestampNumber,
+    mail::notifications::{self, ReviewedItem},
     sql::tables::{
         Category,
         players::Players,
-        scores::Scores,
+        scores::{Scores, ScoresByDate},
         submissions::{SubmissionStatus, Submissions, edit_submissions::EditSubmissions},
     },
 };
@@ -24,13 +31,23 @@ pub fn submissions() -> impl HttpServiceFactory {
     web::scope("/submissions")
         .route(
             "/create_submission",
-            web::post().to(create_or_edit_submission),
+            rate_limited_unless(
+                web::post().to(create_or_edit_submission),
+                "create_submission",
+                &crate::ENV_VARS.rate_limit_submissions,
+                Role::SubmissionReviewer,
+            ),
         )
         .route("/delete_submission", web::post().to(delete_submission))
         .route("/get_submissions", web::post().to(get_submissions))
         .route(
             "/create_edit_submission",
-            web::post().to(create_or_edit_edit_submission),
+            rate_limited_unless(
+                web::post().to(create_or_edit_edit_submission),
+                "create_edit_submission",
+                &crate::ENV_VARS.rate_limit_submissions,
+                Role::SubmissionReviewer,
+            ),
         )
         .route(
             "/delete_edit_submission",
@@ -235,7 +252,12 @@ async fn delete<X: Deletable + for<'a> FromRow<'a, PgRow>>(
     let player_id = submission.get_player_id().await?;
 
     let can_delete = match (
-        is_user_admin(data.validation_data.user_id, &mut executor).await?,
+        user_has_role(
+            data.validation_data.user_id,
+            Role::SubmissionReviewer,
+            &mut executor,
+        )
+        .await?,
         get_user_data(&data.validation_data.session_token, &mut executor)
             .await?
             .player_id,
@@ -291,10 +313,18 @@ async fn create_or_edit_submission(
     let data = data.0;
 
     let app_state = access_app_state().await;
-    let mut executor = {
+    let (live_feed_open, mut executor) = {
         let app_state = app_state.read().await;
-        app_state.acquire_pg_connection().await?
+        (
+            app_state.live_feed.has_subscribers(),
+            app_state
+                .pg_pool
+                .begin()
+                .await
+                .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?,
+        )
     };
+    let mut events = Vec::new();
 
     if !is_valid_token(
         &data.validation_data.session_token,
@@ -310,9 +340,14 @@ async fn create_or_edit_submission(
         return Err(EveryReturnedError::MismatchedIds.into_final_error(""));
     }
 
-    let is_admin = is_user_admin(data.validation_data.user_id, &mut executor).await?;
+    let is_reviewer = user_has_role(
+        data.validation_data.user_id,
+        Role::SubmissionReviewer,
+        &mut executor,
+    )
+    .await?;
     let can_submit = match (
-        is_admin,
+        is_reviewer,
         get_user_data(&data.validation_data.session_token, &mut executor)
             .await?
             .player_id,
@@ -328,13 +363,48 @@ async fn create_or_edit_submission(
         return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
     }
 
-    Submissions::create_or_edit_submission(data.data.clone(), is_admin, &mut executor).await?;
+    let previous_status = match (is_reviewer, data.data.submission_id) {
+        (true, Some(id)) => Some(
+            decode_row_to_table::<Submissions>(
+                Submissions::get_submission_by_id(id, &mut executor).await?,
+            )?
+            .status,
+        ),
+        _ => None,
+    };
+
+    let submission_id =
+        Submissions::create_or_edit_submission(data.data.clone(), is_reviewer, &mut executor)
+            .await?;
+
+    if data.data.submission_id.is_none() {
+        crate::webhooks::submission_created(&mut executor, &data.data).await?;
+
+        if live_feed_open {
+            events.push(LiveEvent {
+                kind: LiveEventKind::Submission,
+                data: ScoresByDate::get_submission(&mut executor, submission_id).await?,
+            });
+        }
+    }
+
+    if let (Some(id), Some(previous_status), Some(status)) =
+        (data.data.submission_id, previous_status, &data.data.status)
+        && previous_status != *status
+    {
+        let reviewer_id = data.validation_data.user_id;
+        notifications::send_or_log(&mut executor, "submission review", async |executor| {
+            notifications::submission_reviewed(executor, ReviewedItem::Submission, id, reviewer_id)
+                .await
+        })
+        .await;
+    }
 
-    if is_admin
+    if is_reviewer
         && let Some(status) = data.data.status
         && status == SubmissionStatus::Accepted
     {
-        Scores::insert_or_edit(
+        let (score_id, mut score_events) = Scores::insert_or_edit(
             None,
             data.data.value,
             data.data.category,
@@ -346,11 +416,21 @@ async fn create_or_edit_submission(
             data.data.ghost_link,
             data.data.comment,
             None,
+            live_feed_open,
             &mut executor,
         )
         .await?;
+        Submissions::set_score_id(submission_id, score_id, &mut executor).await?;
+        events.append(&mut score_events);
     }
 
+    executor
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    app_state.read().await.live_feed.publish(events);
+
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .body("{}"))
@@ -384,10 +464,18 @@ async fn create_or_edit_edit_submission(
     let mut data = data.0;
 
     let app_state = access_app_state().await;
-    let mut executor = {
+    let (live_feed_open, mut executor) = {
         let app_state = app_state.read().await;
-        app_state.acquire_pg_connection().await?
+        (
+            app_state.live_feed.has_subscribers(),
+            app_state
+                .pg_pool
+                .begin()
+                .await
+                .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?,
+        )
     };
+    let mut events = Vec::new();
 
     if !is_valid_token(
         &data.validation_data.session_token,
@@ -421,10 +509,15 @@ async fn create_or_edit_edit_submission(
         return Err(EveryReturnedError::NothingChanged.into_final_error(""));
     }
 
-    let is_admin = is_user_admin(data.validation_data.user_id, &mut executor).await?;
+    let is_reviewer = user_has_role(
+        data.validation_data.user_id,
+        Role::SubmissionReviewer,
+        &mut executor,
+    )
+    .await?;
 
     let can_submit = match (
-        is_admin,
+        is_reviewer,
         get_user_data(&data.validation_data.session_token, &mut executor)
             .await?
             .player_id,
@@ -440,16 +533,46 @@ async fn create_or_edit_edit_submission(
         return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
     }
 
-    EditSubmissions::create_or_edit_submission(data.data.clone(), is_admin, &mut executor).await?;
+    let previous_status = match (is_reviewer, data.data.edit_submission_id) {
+        (true, Some(id)) => Some(
+            decode_row_to_table::<EditSubmissions>(
+                EditSubmissions::get_edit_submission_by_id(id, &mut executor).await?,
+            )?
+            .status,
+        ),
+        _ => None,
+    };
+
+    EditSubmissions::create_or_edit_submission(data.data.clone(), is_reviewer, &mut executor)
+        .await?;
+
+    if let (Some(id), Some(previous_status), Some(status)) = (
+        data.data.edit_submission_id,
+        previous_status,
+        &data.data.status,
+    ) && previous_status != *status
+    {
+        let reviewer_id = data.validation_data.user_id;
+        notifications::send_or_log(&mut executor, "submission review", async |executor| {
+            notifications::submission_reviewed(
+                executor,
+                ReviewedItem::EditSubmission,
+                id,
+                reviewer_id,
+            )
+            .await
+        })
+        .await;
+    }
 
-    if is_admin
+    if is_reviewer
         && let Some(status) = data.data.status
         && status == SubmissionStatus::Accepted
     {
         let score = decode_row_to_table::<Scores>(
             Scores::get_from_id(data.data.score_id, &mut executor).await?,
         )?;
-        Scores::insert_or_edit(
+        let (_, mut score_events) = Scores::insert_or_edit(
             Some(data.data.score_id),
             score.value,
             score.category,
@@ -461,11 +584,20 @@ async fn create_or_edit_edit_submission(
             data.data.ghost_link,
             data.data.comment,
             score.admin_note,
+            live_feed_open,
             &mut executor,
         )
         .await?;
+        events.append(&mut score_events);
     }
 
+    executor
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    app_state.read().await.live_feed.publish(events);
+
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .body("{}"))
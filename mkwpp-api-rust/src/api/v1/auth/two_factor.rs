@@ -0,0 +1,277 @@
+use actix_web::{HttpRequest, HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::send_serialized_data,
+    },
+    auth::{
+        BareMinimumValidationData, LogInData, extractor::session_cookie, is_valid_token, two_factor,
+    },
+};
+
+pub fn two_factor() -> impl HttpServiceFactory {
+    web::scope("/2fa")
+        .route("/enroll", web::put().to(enroll))
+        .route("/confirm", web::put().to(confirm))
+        .route("/verify", web::put().to(verify))
+        .route("/recovery_codes", web::put().to(recovery_codes))
+        .route("/disable", web::put().to(disable))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!(
+    "/enroll",
+    "/confirm",
+    "/verify",
+    "/recovery_codes",
+    "/disable"
+);
+
+/// Enrolment can be done either while logged in, or while completing
+/// a login challenge for an account that is required to have TOTP
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnrolmentAuth {
+    challenge_token: Option<String>,
+    #[serde(flatten)]
+    validation_data: Option<BareMinimumValidationData>,
+}
+
+impl EnrolmentAuth {
+    async fn get_user_id(
+        &self,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<i32, FinalErrorResponse> {
+        if let Some(challenge_token) = &self.challenge_token {
+            return two_factor::get_challenge_user_id(challenge_token, executor).await;
+        }
+
+        match &self.validation_data {
+            Some(validation_data)
+                if is_valid_token(
+                    &validation_data.session_token,
+                    validation_data.user_id,
+                    executor,
+                )
+                .await? =>
+            {
+                Ok(validation_data.user_id)
+            }
+            _ => Err(EveryReturnedError::InvalidSessionToken.into_final_error("")),
+        }
+    }
+}
+
+async fn enroll(
+    body: web::Json<EnrolmentAuth>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let user_id = body.get_user_id(&mut transaction).await?;
+    let enrolment = two_factor::begin_enrolment(user_id, &mut transaction).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    send_serialized_data(enrolment)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmBody {
+    code: String,
+    #[serde(flatten)]
+    auth: EnrolmentAuth,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmResponse {
+    recovery_codes: Vec<String>,
+    session: Option<LogInData>,
+}
+
+async fn confirm(
+    req: HttpRequest,
+    body: web::Json<ConfirmBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let user_id = body.auth.get_user_id(&mut transaction).await?;
+    let recovery_codes =
+        two_factor::confirm_enrolment(user_id, &body.code, &mut transaction).await?;
+
+    let session = match &body.auth.challenge_token {
+        Some(challenge_token) => Some(
+            two_factor::complete_challenge(
+                challenge_token,
+                user_id,
+                req.peer_addr().unwrap().ip(),
+                super::get_user_agent(&req),
+                &mut transaction,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    let cookie = session.as_ref().map(session_cookie);
+    let response = send_serialized_data(ConfirmResponse {
+        recovery_codes,
+        session,
+    })?;
+
+    match cookie {
+        Some(cookie) => super::with_cookie(response, cookie),
+        None => Ok(response),
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyBody {
+    challenge_token: String,
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+async fn verify(
+    req: HttpRequest,
+    body: web::Json<VerifyBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    let session = two_factor::verify_challenge(
+        &body.challenge_token,
+        body.code.as_deref(),
+        body.recovery_code.as_deref(),
+        req.peer_addr().unwrap().ip(),
+        super::get_user_agent(&req),
+        &mut transaction,
+    )
+    .await;
+
+    // Failed attempts have to be stored for the cooldown
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    let session = session?;
+    let cookie = session_cookie(&session);
+    super::with_cookie(send_serialized_data(session)?, cookie)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeBody {
+    code: String,
+    #[serde(flatten)]
+    validation_data: BareMinimumValidationData,
+}
+
+async fn recovery_codes(
+    body: web::Json<CodeBody>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    two_factor::verify_code(body.validation_data.user_id, &body.code, &mut transaction).await?;
+    let recovery_codes =
+        two_factor::regenerate_recovery_codes(body.validation_data.user_id, &mut transaction)
+            .await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    send_serialized_data(recovery_codes)
+}
+
+async fn disable(body: web::Json<CodeBody>) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let body = body.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut transaction = {
+        let data = data.read().await;
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
+    };
+
+    if !is_valid_token(
+        &body.validation_data.session_token,
+        body.validation_data.user_id,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(EveryReturnedError::InvalidSessionToken.into_final_error(""));
+    }
+
+    two_factor::disable(body.validation_data.user_id, &body.code, &mut transaction).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body("{}"))
+}
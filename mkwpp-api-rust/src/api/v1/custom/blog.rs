@@ -8,17 +8,17 @@ use crate::{
             decode_row_to_table, decode_rows_to_table, send_serialized_data,
         },
     },
-    auth::get_username_by_id,
-    sql::tables::{blog_posts::BlogPosts, players::Players},
+    sql::tables::blog_posts::{BlogListFilter, BlogPosts},
 };
 
 pub fn blog() -> impl HttpServiceFactory {
     web::scope("/blog")
         .route("/list", web::get().to(get_list))
         .route("/id/{number}", web::get().to(get_by_id))
+        .route("/tags", web::get().to(get_tags))
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/list", "/id/:id");
+default_paths_fn!("/list", "/id/:id", "/tags");
 
 async fn get_list(req: HttpRequest) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
     let params = ParamsDestructured::from_query(
@@ -31,19 +31,20 @@ async fn get_list(req: HttpRequest) -> actix_web::Result<HttpResponse, FinalErro
         data.acquire_pg_connection().await?
     };
 
-    let mut data = decode_rows_to_table::<BlogPosts>(
-        BlogPosts::get_limit(params.limit, &mut executor).await?,
+    let data = decode_rows_to_table::<BlogPosts>(
+        BlogPosts::get_list(
+            &mut executor,
+            &BlogListFilter {
+                limit: params.limit,
+                offset: params.offset,
+                tag: params.tag.map(|x| x.trim().to_lowercase()),
+                query: params.query,
+                cursor: params.cursor,
+            },
+        )
+        .await?,
     )?;
 
-    for post in data.iter_mut() {
-        if let Some(author_id) = post.author_id {
-            post.author_id = Players::get_player_id_from_user_id(&mut executor, author_id)
-                .await
-                .ok();
-            post.username = Some(get_username_by_id(author_id, &mut executor).await?);
-        }
-    }
-
     crate::api::v1::close_connection(executor).await?;
 
     send_serialized_data(data)
@@ -55,16 +56,21 @@ async fn get_by_id(path: web::Path<i32>) -> actix_web::Result<HttpResponse, Fina
         data.acquire_pg_connection().await?
     };
 
-    let mut data = decode_row_to_table::<BlogPosts>(
+    let data = decode_row_to_table::<BlogPosts>(
         BlogPosts::get_by_id(path.into_inner(), &mut executor).await?,
     )?;
 
-    if let Some(author_id) = data.author_id {
-        data.author_id = Players::get_player_id_from_user_id(&mut executor, author_id)
-            .await
-            .ok();
-        data.username = Some(get_username_by_id(author_id, &mut executor).await?);
-    }
+    crate::api::v1::close_connection(executor).await?;
+    send_serialized_data(data)
+}
+async fn get_tags() -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut executor = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let data = BlogPosts::get_tags(&mut executor).await?;
 
     crate::api::v1::close_connection(executor).await?;
     send_serialized_data(data)
@@ -0,0 +1,53 @@
+use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    api::{
+        errors::FinalErrorResponse,
+        v1::{close_connection, send_serialized_data},
+    },
+    sql::tables::comments::{CommentTarget, Comments},
+};
+
+pub fn comments() -> impl HttpServiceFactory {
+    web::scope("/comments")
+        .route("/blog/{postId}", web::get().to(get_for_blog_post))
+        .route("/score/{scoreId}", web::get().to(get_for_score))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/blog/:postId", "/score/:scoreId");
+
+async fn get_for_target(
+    target: CommentTarget,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut executor = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let data = Comments::get_for_target(&mut executor, target).await?;
+
+    close_connection(executor).await?;
+
+    send_serialized_data(data)
+}
+
+async fn get_for_blog_post(
+    path: web::Path<i32>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    get_for_target(CommentTarget {
+        blog_post_id: Some(path.into_inner()),
+        score_id: None,
+    })
+    .await
+}
+
+async fn get_for_score(
+    path: web::Path<i32>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    get_for_target(CommentTarget {
+        blog_post_id: None,
+        score_id: Some(path.into_inner()),
+    })
+    .await
+}
@@ -0,0 +1,170 @@
+use std::fmt::Write;
+
+use actix_web::HttpResponse;
+
+/// Escapes text for use inside XML elements and attribute values.
+/// Characters XML 1.0 can't hold at all are dropped
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn timestamp(date: &chrono::DateTime<chrono::Utc>) -> String {
+    date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+pub enum Content {
+    Text(String),
+    Html(String),
+}
+
+pub struct Entry {
+    /// Must never change once published, feed readers use it to spot duplicates
+    pub id: String,
+    pub title: String,
+    pub updated: chrono::DateTime<chrono::Utc>,
+    pub author: Option<String>,
+    pub content: Content,
+}
+
+/// An Atom 1.0 feed, only covering the elements our feeds use
+pub struct Feed {
+    pub title: String,
+    /// URL the feed was requested from, also used as its id
+    pub self_link: String,
+    pub alternate_link: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Feed {
+    pub fn to_xml(&self) -> String {
+        // An empty feed still needs an update date, the epoch says "nothing yet"
+        let updated = self
+            .entries
+            .iter()
+            .map(|x| x.updated)
+            .max()
+            .unwrap_or_default();
+
+        let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+        let _ = write!(
+            xml,
+            r#"<id>{self_link}</id><title>{}</title><updated>{}</updated><link rel="self" href="{self_link}"/><link rel="alternate" href="{}"/><author><name>MKWPP</name></author>"#,
+            escape(&self.title),
+            timestamp(&updated),
+            escape(&self.alternate_link),
+            self_link = escape(&self.self_link),
+        );
+
+        for entry in &self.entries {
+            let _ = write!(
+                xml,
+                "<entry><id>{}</id><title>{}</title><updated>{}</updated>",
+                escape(&entry.id),
+                escape(&entry.title),
+                timestamp(&entry.updated),
+            );
+            if let Some(author) = &entry.author {
+                let _ = write!(xml, "<author><name>{}</name></author>", escape(author));
+            }
+            let _ = match &entry.content {
+                Content::Text(text) => {
+                    write!(xml, r#"<content type="text">{}</content>"#, escape(text))
+                }
+                Content::Html(html) => {
+                    write!(xml, r#"<content type="html">{}</content>"#, escape(html))
+                }
+            };
+            xml.push_str("</entry>");
+        }
+
+        xml.push_str("</feed>");
+        xml
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type("application/atom+xml; charset=utf-8")
+            .body(self.to_xml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::{Content, Entry, Feed, escape};
+
+    fn feed(entries: Vec<Entry>) -> Feed {
+        Feed {
+            title: String::from("Records & <more>"),
+            self_link: String::from("https://example.com/feeds/records?a=1&b=2"),
+            alternate_link: String::from("https://example.com/"),
+            entries,
+        }
+    }
+
+    #[test]
+    fn escapes_markup_and_quotes() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape("&amp;"), "&amp;amp;", "entities aren't trusted");
+    }
+
+    #[test]
+    fn drops_characters_xml_cannot_hold() {
+        assert_eq!(escape("a\u{0}b\u{8}c\u{1B}d\u{FFFF}"), "abcd");
+        assert_eq!(escape("a\tb\nc\rd é 🏁"), "a\tb\nc\rd é 🏁");
+    }
+
+    #[test]
+    fn escapes_every_field_once() {
+        let xml = feed(vec![
+            Entry {
+                id: String::from("urn:mkwpp:score:1"),
+                title: String::from("<b>New</b> record"),
+                updated: chrono::Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap(),
+                author: Some(String::from("A&B")),
+                content: Content::Html(String::from("<p>1'02\"345</p>")),
+            },
+            Entry {
+                id: String::from("urn:mkwpp:score:2"),
+                title: String::from("Plain"),
+                updated: chrono::Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                author: None,
+                content: Content::Text(String::from("<not html>")),
+            },
+        ])
+        .to_xml();
+
+        assert!(xml.contains(
+            r#"<id>https://example.com/feeds/records?a=1&amp;b=2</id><title>Records &amp; &lt;more&gt;</title><updated>2025-01-02T03:04:05Z</updated><link rel="self" href="https://example.com/feeds/records?a=1&amp;b=2"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<title>&lt;b&gt;New&lt;/b&gt; record</title><updated>2025-01-02T03:04:05Z</updated><author><name>A&amp;B</name></author><content type="html">&lt;p&gt;1&apos;02&quot;345&lt;/p&gt;</content>"#
+        ));
+        assert!(xml.contains(r#"<content type="text">&lt;not html&gt;</content></entry></feed>"#));
+    }
+
+    #[test]
+    fn dates_empty_feeds_at_the_epoch() {
+        let xml = feed(vec![]).to_xml();
+        assert!(xml.contains("<updated>1970-01-01T00:00:00Z</updated>"));
+        assert!(!xml.contains("<entry>"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use actix_web::{HttpRequest, HttpResponse, dev::HttpServiceFactory, web};
+
+use crate::{
+    ENV_VARS,
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{close_connection, decode_rows_to_table},
+    },
+    mail::notifications::{category_name, chart_name, format_time},
+    sql::tables::{
+        BasicTableQueries,
+        blog_posts::{BlogListFilter, BlogPosts},
+        players::Players,
+        regions::Regions,
+        scores::ScoresByDate,
+        tracks::Tracks,
+    },
+};
+
+use atom::{Content, Entry, Feed};
+
+mod atom;
+
+/// Entries in each feed, readers only care about what's new
+const FEED_LENGTH: i32 = 50;
+
+pub fn feeds() -> impl HttpServiceFactory {
+    web::scope("/feeds")
+        .route("/blog", web::get().to(blog))
+        .route("/records", web::get().to(records))
+        .route("/player/{playerId}", web::get().to(player))
+        .default_service(web::get().to(default))
+}
+default_paths_fn!("/blog", "/records", "/player/:playerId");
+
+fn site_link() -> String {
+    format!("{}/mkw/", ENV_VARS.server_dns)
+}
+
+/// Scores only store the day they were set on, so entries are dated at midnight
+fn score_date(score: &ScoresByDate) -> chrono::DateTime<chrono::Utc> {
+    score
+        .date
+        .and_then(|x| x.and_hms_opt(0, 0, 0))
+        .map(|x| x.and_utc())
+        .unwrap_or_default()
+}
+
+async fn score_entries(
+    executor: &mut sqlx::PgConnection,
+    scores: Vec<(ScoresByDate, chrono::DateTime<chrono::Utc>)>,
+) -> Result<Vec<Entry>, FinalErrorResponse> {
+    let tracks = decode_rows_to_table::<Tracks>(Tracks::select_star_query(executor).await?)?
+        .into_iter()
+        .map(|x| (x.id, x.abbr))
+        .collect::<HashMap<_, _>>();
+
+    Ok(scores
+        .into_iter()
+        .map(|(score, updated)| {
+            let chart = chart_name(
+                tracks
+                    .get(&score.track_id)
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                score.is_lap,
+            );
+            let time = format_time(score.value);
+            let category = category_name(&score.category);
+            Entry {
+                id: format!(
+                    "urn:mkwpp:score:{}:{}:{}:{}:{}",
+                    score.player.id,
+                    score.track_id,
+                    score.is_lap,
+                    u8::from(&score.category),
+                    score.value
+                ),
+                title: format!("{} - {chart} {category} - {time}", score.player.name),
+                updated,
+                author: Some(score.player.name.clone()),
+                content: Content::Text(format!(
+                    "{} set {time} on {chart} ({category})",
+                    score.player.name
+                )),
+            }
+        })
+        .collect())
+}
+
+async fn blog(req: HttpRequest) -> Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut executor = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let entries = decode_rows_to_table::<BlogPosts>(
+        BlogPosts::get_list(
+            &mut executor,
+            &BlogListFilter {
+                limit: FEED_LENGTH,
+                ..Default::default()
+            },
+        )
+        .await?,
+    )?
+    .into_iter()
+    .map(|post| Entry {
+        id: format!("urn:mkwpp:blog:{}", post.id),
+        title: post.title,
+        updated: post.published_at,
+        author: post.username,
+        content: Content::Html(post.content),
+    })
+    .collect();
+
+    close_connection(executor).await?;
+
+    Ok(Feed {
+        title: String::from("MKWPP Blog"),
+        self_link: req.full_url().to_string(),
+        alternate_link: site_link(),
+        entries,
+    }
+    .into_response())
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordsQuery {
+    region_id: Option<i32>,
+}
+
+/// World records, optionally only the ones set by players from a region
+async fn records(
+    req: HttpRequest,
+    query: web::Query<RecordsQuery>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut executor = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let rows = match query.region_id {
+        None => ScoresByDate::order_records_by_date(&mut executor, FEED_LENGTH).await?,
+        Some(region_id) => {
+            let region_ids = Regions::get_contained(&mut executor, region_id).await?;
+            if region_ids.is_empty() {
+                return Err(EveryReturnedError::InvalidInput.into_final_error("Unknown region"));
+            }
+            ScoresByDate::order_region_records_by_date(&mut executor, &region_ids, FEED_LENGTH)
+                .await?
+        }
+    };
+    let scores = decode_rows_to_table::<ScoresByDate>(rows)?
+        .into_iter()
+        .map(|score| {
+            let date = score_date(&score);
+            (score, date)
+        })
+        .collect();
+    let entries = score_entries(&mut executor, scores).await?;
+
+    close_connection(executor).await?;
+
+    Ok(Feed {
+        title: String::from("MKWPP World Records"),
+        self_link: req.full_url().to_string(),
+        alternate_link: site_link(),
+        entries,
+    }
+    .into_response())
+}
+
+async fn player(
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, FinalErrorResponse> {
+    let player_id = path.into_inner();
+
+    let data = crate::app_state::access_app_state().await;
+    let mut executor = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let name = Players::get_name(&mut executor, player_id).await?;
+    let scores =
+        ScoresByDate::order_player_by_acceptance(&mut executor, player_id, FEED_LENGTH).await?;
+    let entries = score_entries(&mut executor, scores).await?;
+
+    close_connection(executor).await?;
+
+    Ok(Feed {
+        title: format!("MKWPP - {name}"),
+        self_link: req.full_url().to_string(),
+        alternate_link: site_link(),
+        entries,
+    }
+    .into_response())
+}
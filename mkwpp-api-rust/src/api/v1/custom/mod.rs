@@ -1,6 +1,8 @@
 use actix_web::{dev::HttpServiceFactory, web};
 
 mod blog;
+mod comments;
+mod feeds;
 pub mod params;
 mod players;
 mod rankings;
@@ -15,6 +17,8 @@ pub fn custom() -> impl HttpServiceFactory {
         .service(regions::regions())
         .service(players::players())
         .service(blog::blog())
+        .service(comments::comments())
+        .service(feeds::feeds())
         .service(site_champs::site_champs())
         .default_service(web::get().to(default))
 }
@@ -23,5 +27,7 @@ default_paths_fn!(
     "/rankings",
     "/regions",
     "/players",
+    "/comments",
+    "/feeds",
     "/site_champs"
 );
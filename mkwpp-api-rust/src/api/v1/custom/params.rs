@@ -7,7 +7,11 @@ pub struct Params {
     dat: Option<String>,
     reg: Option<i32>,
     lim: Option<i32>,
+    off: Option<i32>,
+    cur: Option<i32>,
+    tag: Option<String>,
     rty: Option<u8>,
+    qry: Option<String>,
 }
 
 pub struct ParamsDestructured {
@@ -16,7 +20,12 @@ pub struct ParamsDestructured {
     pub date: chrono::NaiveDate,
     pub region_id: i32,
     pub limit: i32,
+    pub offset: i32,
+    /// Id of the last item already seen, for cursor pagination
+    pub cursor: Option<i32>,
+    pub tag: Option<String>,
     pub region_type: RegionType,
+    pub query: String,
 }
 
 impl ParamsDestructured {
@@ -34,10 +43,14 @@ impl ParamsDestructured {
             region_id: params.reg.unwrap_or(1),
             lap_mode: params.lap.map(|x| x == 1),
             limit: params.lim.unwrap_or(i32::MAX),
+            offset: params.off.unwrap_or(0).max(0),
+            cursor: params.cur,
+            tag: params.tag,
             region_type: params
                 .rty
                 .and_then(|x| RegionType::try_from(x).ok())
                 .unwrap_or(RegionType::World),
+            query: params.qry.unwrap_or_default(),
         }
     }
 }
@@ -1,8 +1,13 @@
 use crate::{
-    api::errors::FinalErrorResponse,
-    sql::tables::players::{FilterPlayers, Players, players_basic::PlayersBasic},
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::custom::params::{Params, ParamsDestructured},
+    },
+    sql::tables::players::{
+        FilterPlayers, Players, players_basic::PlayersBasic, profile::PlayerProfile,
+    },
 };
-use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use actix_web::{HttpRequest, HttpResponse, dev::HttpServiceFactory, web};
 
 pub fn players() -> impl HttpServiceFactory {
     web::scope("/players")
@@ -15,9 +20,17 @@ pub fn players() -> impl HttpServiceFactory {
             "/select_basic",
             web::post().to(get_with_decode::<PlayersBasic>),
         )
+        .route("/search", web::get().to(search))
+        .route("/{player_id}/profile", web::get().to(get_profile))
         .default_service(web::get().to(default))
 }
-default_paths_fn!("/list", "/select", "/select_basic");
+default_paths_fn!(
+    "/list",
+    "/select",
+    "/select_basic",
+    "/search",
+    "/:playerId/profile"
+);
 
 pub async fn get_with_decode<
     Table: for<'a> sqlx::FromRow<'a, sqlx::postgres::PgRow> + serde::Serialize + FilterPlayers,
@@ -30,3 +43,56 @@ pub async fn get_with_decode<
     })
     .await;
 }
+
+pub async fn get_profile(
+    path: web::Path<i32>,
+) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let data = PlayerProfile::get(&mut connection, path.into_inner()).await?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    crate::api::v1::send_serialized_data(data)
+}
+
+const MAX_SEARCH_RESULTS: i32 = 50;
+
+pub async fn search(req: HttpRequest) -> actix_web::Result<HttpResponse, FinalErrorResponse> {
+    let params = ParamsDestructured::from_query(
+        web::Query::<Params>::from_query(req.query_string()).unwrap(),
+    );
+
+    let query = params.query.trim();
+    if query.is_empty() {
+        return Err(EveryReturnedError::InvalidInput.into_final_error("Search query is empty"));
+    }
+
+    let data = crate::app_state::access_app_state().await;
+    let mut connection = {
+        let data = data.read().await;
+        data.acquire_pg_connection().await?
+    };
+
+    let region_ids =
+        crate::sql::tables::regions::Regions::get_descendants(&mut connection, params.region_id)
+            .await?;
+
+    let data = crate::api::v1::decode_rows_to_table::<PlayersBasic>(
+        PlayersBasic::search(
+            &mut connection,
+            query,
+            region_ids,
+            params.limit.clamp(1, MAX_SEARCH_RESULTS),
+        )
+        .await?,
+    )?;
+
+    crate::api::v1::close_connection(connection).await?;
+
+    crate::api::v1::send_serialized_data(data)
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashSet, sync::Arc};
+
+use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    app_state::{access_app_state, live::LiveEvent},
+    sql::tables::regions::Regions,
+};
+
+/// Proxies tend to close connections that stay silent for too long
+const KEEP_ALIVE_INTERVAL: core::time::Duration = core::time::Duration::from_secs(15);
+
+pub fn live() -> impl HttpServiceFactory {
+    web::resource("/live").route(web::get().to(get))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveQuery {
+    region_id: Option<i32>,
+    track_id: Option<i32>,
+}
+
+struct LiveFilter {
+    /// The requested region and every region inside it
+    regions: Option<HashSet<i32>>,
+    track_id: Option<i32>,
+}
+
+impl LiveFilter {
+    fn matches(&self, event: &LiveEvent) -> bool {
+        self.track_id.is_none_or(|x| x == event.data.track_id)
+            && self
+                .regions
+                .as_ref()
+                .is_none_or(|x| x.contains(&event.data.player.region_id))
+    }
+}
+
+fn format_event(event: &LiveEvent) -> Option<web::Bytes> {
+    let data = serde_json::to_string(&event.data).ok()?;
+    Some(web::Bytes::from(format!(
+        "event: {}\ndata: {data}\n\n",
+        event.kind.as_str()
+    )))
+}
+
+async fn next_message(
+    receiver: &mut Receiver<Arc<LiveEvent>>,
+    filter: &LiveFilter,
+) -> Option<web::Bytes> {
+    loop {
+        let event = tokio::select! {
+            event = receiver.recv() => event,
+            _ = tokio::time::sleep(KEEP_ALIVE_INTERVAL) => {
+                return Some(web::Bytes::from_static(b": keep-alive\n\n"));
+            }
+        };
+
+        match event {
+            Ok(event) if filter.matches(&event) => {
+                if let Some(message) = format_event(&event) {
+                    return Some(message);
+                }
+            }
+            Ok(_) => (),
+            // Slow clients miss events instead of holding the channel back
+            Err(RecvError::Lagged(_)) => (),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Pushes `score`, `record` and `submission` events as Server-Sent Events,
+/// each carrying a score in the same shape as `/recent`
+async fn get(query: web::Query<LiveQuery>) -> Result<HttpResponse, FinalErrorResponse> {
+    let query = query.into_inner();
+
+    let app_state = access_app_state().await;
+    let app_state = app_state.read().await;
+
+    let regions = match query.region_id {
+        None => None,
+        Some(region_id) => {
+            let mut executor = app_state.acquire_pg_connection().await?;
+            let regions = Regions::get_contained(&mut executor, region_id).await?;
+            if regions.is_empty() {
+                return Err(EveryReturnedError::InvalidInput.into_final_error("Unknown region"));
+            }
+            Some(regions.into_iter().collect::<HashSet<_>>())
+        }
+    };
+
+    let filter = LiveFilter {
+        regions,
+        track_id: query.track_id,
+    };
+    let receiver = app_state.live_feed.subscribe();
+
+    let stream = futures::stream::unfold((receiver, filter), async |(mut receiver, filter)| {
+        let message = next_message(&mut receiver, &filter).await?;
+        Some((Ok::<_, actix_web::Error>(message), (receiver, filter)))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(stream))
+}
@@ -1,6 +1,7 @@
 use actix_web::{dev::HttpServiceFactory, web};
 
 mod chart;
+mod live;
 mod recent;
 mod records;
 mod timesheet;
@@ -8,6 +9,7 @@ mod timesheet;
 pub fn scores() -> impl HttpServiceFactory {
     web::scope("/scores")
         .service(recent::recent())
+        .service(live::live())
         .service(chart::chart())
         .service(records::records())
         .service(timesheet::timesheet())
@@ -16,6 +18,7 @@ pub fn scores() -> impl HttpServiceFactory {
 }
 default_paths_fn!(
     "/recent",
+    "/live",
     "/chart/:trackId",
     "/timesheet/:playerId",
     "/records",
@@ -1,6 +1,6 @@
 use crate::{
     api::errors::{EveryReturnedError, FinalErrorResponse},
-    auth::is_user_admin,
+    auth::extractor::AdminUser,
     sql::tables::BasicTableQueries,
 };
 use actix_web::{HttpResponse, dev::HttpServiceFactory, web};
@@ -25,6 +25,7 @@ macro_rules! default_paths_fn {
 mod admin;
 pub mod auth;
 mod custom;
+mod rate_limit;
 mod raw;
 
 pub fn v1() -> impl HttpServiceFactory {
@@ -150,36 +151,32 @@ pub async fn get_star_query<
 #[serde(rename_all = "camelCase")]
 struct DeleteBody {
     id: i32,
-    session_token: String,
 }
 
+/// Only mounted inside admin scopes, whose role guard also reads the session token from the body
 async fn delete_by_id<
     Table: for<'a> sqlx::FromRow<'a, sqlx::postgres::PgRow> + serde::Serialize + BasicTableQueries,
 >(
+    _user: AdminUser,
     body: web::Json<DeleteBody>,
 ) -> Result<HttpResponse, FinalErrorResponse> {
     let body = body.into_inner();
 
     let data = crate::app_state::access_app_state().await;
-    let mut connection = {
+    let mut transaction = {
         let data = data.read().await;
-        data.acquire_pg_connection().await?
+        data.pg_pool
+            .begin()
+            .await
+            .map_err(|e| EveryReturnedError::CreatePGTransaction.into_final_error(e))?
     };
 
-    if !is_user_admin(
-        crate::auth::get_user_data(&body.session_token, &mut connection)
-            .await?
-            .user_id,
-        &mut connection,
-    )
-    .await?
-    {
-        return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
-    }
-
-    Table::delete_by_id(body.id, &mut connection).await?;
+    Table::delete_by_id(body.id, &mut transaction).await?;
 
-    close_connection(connection).await?;
+    transaction
+        .commit()
+        .await
+        .map_err(|e| EveryReturnedError::CommitPGTransaction.into_final_error(e))?;
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
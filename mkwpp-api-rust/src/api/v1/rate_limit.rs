@@ -0,0 +1,142 @@
+use actix_web::{
+    ResponseError, Route,
+    body::BoxBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderValue, RETRY_AFTER},
+    middleware::{self, Next},
+    web,
+};
+
+use crate::{
+    api::{errors::EveryReturnedError, v1::close_connection},
+    app_state::rate_limit::{RateLimit, RateLimiter},
+    auth::{extractor::get_session_token, roles::Role},
+};
+
+/// Wraps a route in [`rate_limit`], `setting` being one of the `RATE_LIMIT_*` values
+pub fn rate_limited(route: Route, name: &'static str, setting: &'static str) -> Route {
+    route.wrap(middleware::from_fn(move |req, next| {
+        rate_limit(name, setting, None, req, next)
+    }))
+}
+
+/// Same as [`rate_limited`], but users holding `exempt_role` are never limited
+pub fn rate_limited_unless(
+    route: Route,
+    name: &'static str,
+    setting: &'static str,
+    exempt_role: Role,
+) -> Route {
+    route.wrap(middleware::from_fn(move |req, next| {
+        rate_limit(name, setting, Some(exempt_role), req, next)
+    }))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionTokenBody {
+    session_token: String,
+}
+
+/// Per-route rate limiting, used through `middleware::from_fn`.
+/// Requests are counted for the IP, and also for the user when a session
+/// token is found in the header/cookie or in the JSON body
+pub async fn rate_limit(
+    route: &'static str,
+    setting: &'static str,
+    exempt_role: Option<Role>,
+    mut req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(limit) = RateLimit::from_setting(setting) else {
+        return next.call(req).await;
+    };
+
+    let session_token = match get_session_token(req.request()) {
+        Some(v) => Some(v),
+        None => {
+            let body = req.extract::<web::Bytes>().await?;
+            req.set_payload(body.clone().into());
+            serde_json::from_slice::<SessionTokenBody>(&body)
+                .ok()
+                .map(|x| x.session_token)
+        }
+    };
+
+    let mut keys = vec![];
+    if let Some(addr) = req.peer_addr() {
+        keys.push(format!("ip:{}", addr.ip()));
+    }
+
+    let app_state = crate::app_state::access_app_state().await;
+
+    let mut executor = match session_token.is_some() || crate::ENV_VARS.rate_limit_postgres {
+        true => {
+            let app_state = app_state.read().await;
+            Some(app_state.acquire_pg_connection().await?)
+        }
+        false => None,
+    };
+
+    let user: Option<(i32, Vec<Role>)> = match (&session_token, &mut executor) {
+        (Some(session_token), Some(executor)) => sqlx::query_as(
+            r#"
+                SELECT
+                    user_id,
+                    ARRAY(
+                        SELECT role
+                        FROM user_roles
+                        WHERE user_roles.user_id = auth_tokens.user_id
+                    ) AS roles
+                FROM auth_tokens
+                WHERE
+                    session_token = $1 AND
+                    expiry >= NOW()
+            "#,
+        )
+        .bind(session_token)
+        .fetch_optional(&mut **executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?,
+        _ => None,
+    };
+
+    if let Some((user_id, roles)) = user {
+        if exempt_role.is_some_and(|x| x.is_granted_by(&roles)) {
+            if let Some(executor) = executor {
+                close_connection(executor).await?;
+            }
+            return next.call(req).await;
+        }
+        keys.push(format!("user:{user_id}"));
+    }
+
+    let mut retry_after = None;
+    for key in keys {
+        let wait = match (&mut executor, crate::ENV_VARS.rate_limit_postgres) {
+            (Some(executor), true) => {
+                RateLimiter::hit_postgres(executor, route, key, limit).await?
+            }
+            _ => app_state.read().await.rate_limiter.hit(route, key, limit),
+        };
+        retry_after = retry_after.max(wait);
+    }
+
+    if let Some(executor) = executor {
+        close_connection(executor).await?;
+    }
+
+    match retry_after {
+        None => next.call(req).await,
+        Some(retry_after) => {
+            let mut response = EveryReturnedError::TooManyRequests
+                .into_final_error("")
+                .error_response();
+            response.headers_mut().insert(
+                RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs_f64().ceil() as u64),
+            );
+            Ok(req.into_response(response))
+        }
+    }
+}
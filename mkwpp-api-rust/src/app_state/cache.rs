@@ -56,6 +56,48 @@ pub async fn update_loop() {
         };
 
         let _ = sqlx::query("DELETE FROM tokens WHERE token_type = 'password_reset'::token_type AND time < NOW() - INTERVAL '15 minutes'").execute(&mut *executor).await;
+        let _ = sqlx::query("DELETE FROM tokens WHERE token_type = 'two_factor_challenge'::token_type AND time < NOW() - INTERVAL '10 minutes'").execute(&mut *executor).await;
+        let _ = sqlx::query("DELETE FROM tokens WHERE token_type = 'email_change'::token_type AND time < NOW() - INTERVAL '24 hours'").execute(&mut *executor).await;
+        let _ = sqlx::query("DELETE FROM auth_tokens WHERE expiry < NOW()")
+            .execute(&mut *executor)
+            .await;
+        let _ = sqlx::query("DELETE FROM rate_limit_counters WHERE expires_at < NOW()")
+            .execute(&mut *executor)
+            .await;
+        app_state.read().await.rate_limiter.prune();
+        let _ = sqlx::query("DELETE FROM email_outbox WHERE status = 'sent'::email_status AND sent_at < NOW() - make_interval(days => $1)")
+            .bind(crate::ENV_VARS.email_sent_expiry as i32)
+            .execute(&mut *executor)
+            .await;
+        let _ = sqlx::query("DELETE FROM webhook_deliveries WHERE status != 'pending'::webhook_delivery_status AND created < NOW() - make_interval(days => $1)")
+            .bind(crate::ENV_VARS.webhook_delivery_expiry as i32)
+            .execute(&mut *executor)
+            .await;
+        let _ = sqlx::query("DELETE FROM tokens WHERE token_type = 'activation'::token_type AND time < NOW() - make_interval(hours => $1)")
+            .bind(crate::ENV_VARS.activation_token_expiry as i32)
+            .execute(&mut *executor)
+            .await;
+        if let Ok(mut transaction) = sqlx::Connection::begin(&mut *executor).await
+            && crate::auth::cleanup_unverified_accounts(&mut transaction)
+                .await
+                .is_ok()
+        {
+            let _ = transaction.commit().await;
+        }
+        if let Ok(mut transaction) = sqlx::Connection::begin(&mut *executor).await
+            && crate::mail::notifications::site_champions(&mut transaction)
+                .await
+                .is_ok()
+        {
+            let _ = transaction.commit().await;
+        }
+        if let Ok(mut transaction) = sqlx::Connection::begin(&mut *executor).await
+            && crate::webhooks::blog_posts_published(&mut transaction)
+                .await
+                .is_ok()
+        {
+            let _ = transaction.commit().await;
+        }
 
         update_loop_if_let_ok!(Standards, standards, executor, app_state);
         update_loop_if_let_ok!(StandardLevels, legacy_standard_levels, executor, app_state);
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use crate::sql::tables::scores::ScoresByDate;
+
+/// Events buffered for each subscriber before it starts missing them
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, Debug)]
+pub enum LiveEventKind {
+    Score,
+    Record,
+    Submission,
+}
+
+impl LiveEventKind {
+    /// Name of the Server-Sent Event
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Score => "score",
+            Self::Record => "record",
+            Self::Submission => "submission",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LiveEvent {
+    pub kind: LiveEventKind,
+    pub data: ScoresByDate,
+}
+
+/// Broadcasts newly accepted scores, records and submissions to every open stream
+pub struct LiveFeed {
+    sender: broadcast::Sender<Arc<LiveEvent>>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl LiveFeed {
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<LiveEvent>> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Only call this once the transaction the events were read from has committed
+    pub fn publish(&self, events: Vec<LiveEvent>) {
+        for event in events {
+            // Nobody listening is not an error
+            let _ = self.sender.send(Arc::new(event));
+        }
+    }
+}
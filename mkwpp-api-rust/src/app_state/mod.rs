@@ -7,10 +7,14 @@ use crate::{
 };
 
 pub mod cache;
+pub mod live;
+pub mod rate_limit;
 
 pub struct AppState {
     pub pg_pool: sqlx::Pool<sqlx::Postgres>,
     pub cache: cache::Cache,
+    pub rate_limiter: rate_limit::RateLimiter,
+    pub live_feed: live::LiveFeed,
 }
 
 impl AppState {
@@ -44,7 +48,12 @@ pub async fn access_app_state() -> &'static RwLock<AppState> {
 
             let cache = cache::Cache::default();
 
-            let app_state = AppState { pg_pool, cache };
+            let app_state = AppState {
+                pg_pool,
+                cache,
+                rate_limiter: rate_limit::RateLimiter::default(),
+                live_feed: live::LiveFeed::default(),
+            };
 
             RwLock::new(app_state)
         })
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+/// A limit of `requests` per `period`, read from settings like `5/3600`
+#[derive(Clone, Copy)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Empty or invalid settings disable the limit
+    pub fn from_setting(setting: &str) -> Option<Self> {
+        let (requests, seconds) = setting.split_once('/')?;
+        let requests: u32 = requests.trim().parse().ok()?;
+        let seconds: u64 = seconds.trim().parse().ok()?;
+        if requests == 0 || seconds == 0 {
+            return None;
+        }
+
+        Some(Self {
+            requests,
+            period: Duration::from_secs(seconds),
+        })
+    }
+}
+
+struct Window {
+    count: u32,
+    end: Instant,
+}
+
+/// Fixed window counters, keyed by route and by IP or user
+#[derive(Default)]
+pub struct RateLimiter {
+    counters: Mutex<HashMap<(&'static str, String), Window>>,
+}
+
+impl RateLimiter {
+    /// Counts a request, returning how long to wait if the limit has been exceeded
+    pub fn hit(&self, route: &'static str, key: String, limit: RateLimit) -> Option<Duration> {
+        self.hit_at(route, key, limit, Instant::now())
+    }
+
+    fn hit_at(
+        &self,
+        route: &'static str,
+        key: String,
+        limit: RateLimit,
+        now: Instant,
+    ) -> Option<Duration> {
+        let mut counters = self.counters.lock().unwrap();
+
+        let window = counters
+            .entry((route, key))
+            .and_modify(|window| {
+                if window.end <= now {
+                    window.count = 0;
+                    window.end = now + limit.period;
+                }
+                window.count += 1;
+            })
+            .or_insert(Window {
+                count: 1,
+                end: now + limit.period,
+            });
+
+        (window.count > limit.requests).then(|| window.end - now)
+    }
+
+    pub fn prune(&self) {
+        self.prune_at(Instant::now());
+    }
+
+    fn prune_at(&self, now: Instant) {
+        self.counters
+            .lock()
+            .unwrap()
+            .retain(|_, window| window.end > now);
+    }
+
+    /// Same as [`RateLimiter::hit`], used instead when `RATE_LIMIT_POSTGRES` is set
+    pub async fn hit_postgres(
+        executor: &mut sqlx::PgConnection,
+        route: &'static str,
+        key: String,
+        limit: RateLimit,
+    ) -> Result<Option<Duration>, FinalErrorResponse> {
+        let (count, seconds_left): (i32, f64) = sqlx::query_as(
+            r#"
+                INSERT INTO rate_limit_counters (route, key, count, expires_at)
+                VALUES ($1, $2, 1, NOW() + make_interval(secs => $3))
+                ON CONFLICT (route, key) DO UPDATE SET
+                    count = CASE
+                        WHEN rate_limit_counters.expires_at <= NOW() THEN 1
+                        ELSE rate_limit_counters.count + 1
+                    END,
+                    expires_at = CASE
+                        WHEN rate_limit_counters.expires_at <= NOW() THEN EXCLUDED.expires_at
+                        ELSE rate_limit_counters.expires_at
+                    END
+                RETURNING
+                    count,
+                    EXTRACT(EPOCH FROM expires_at - NOW())::DOUBLE PRECISION
+            "#,
+        )
+        .bind(route)
+        .bind(key)
+        .bind(limit.period.as_secs_f64())
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok((count as u32 > limit.requests).then(|| Duration::from_secs_f64(seconds_left.max(0.0))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RateLimit, RateLimiter};
+
+    const LIMIT: RateLimit = RateLimit {
+        requests: 2,
+        period: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn parses_settings() {
+        let limit = RateLimit::from_setting(" 5 / 3600 ").unwrap();
+        assert_eq!(limit.requests, 5);
+        assert_eq!(limit.period, Duration::from_secs(3600));
+
+        for setting in ["", "5", "0/60", "5/0", "-1/60", "five/60"] {
+            assert!(RateLimit::from_setting(setting).is_none(), "{setting:?}");
+        }
+    }
+
+    #[test]
+    fn waits_until_the_window_ends() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        let hit = |seconds| {
+            limiter.hit_at(
+                "route",
+                String::from("ip"),
+                LIMIT,
+                start + Duration::from_secs(seconds),
+            )
+        };
+
+        assert_eq!(hit(0), None);
+        assert_eq!(hit(10), None);
+        assert_eq!(hit(20), Some(Duration::from_secs(40)));
+        assert_eq!(hit(59), Some(Duration::from_secs(1)));
+
+        // The window is fixed, so it restarts from the first request after it ended
+        assert_eq!(hit(60), None);
+        assert_eq!(hit(100), None);
+        assert_eq!(hit(110), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn counts_routes_and_keys_separately() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+        for _ in 0..LIMIT.requests {
+            assert_eq!(limiter.hit_at("a", String::from("ip"), LIMIT, now), None);
+        }
+
+        assert!(
+            limiter
+                .hit_at("a", String::from("ip"), LIMIT, now)
+                .is_some()
+        );
+        assert_eq!(limiter.hit_at("b", String::from("ip"), LIMIT, now), None);
+        assert_eq!(limiter.hit_at("a", String::from("user"), LIMIT, now), None);
+    }
+
+    #[test]
+    fn prunes_ended_windows() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+        limiter.hit_at("a", String::from("old"), LIMIT, now);
+        limiter.hit_at(
+            "a",
+            String::from("new"),
+            LIMIT,
+            now + Duration::from_secs(30),
+        );
+
+        limiter.prune_at(now + Duration::from_secs(60));
+        let counters = limiter.counters.lock().unwrap();
+        assert_eq!(counters.len(), 1);
+        assert!(counters.contains_key(&("a", String::from("new"))));
+    }
+}
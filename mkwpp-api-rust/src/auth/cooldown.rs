@@ -6,7 +6,8 @@ use crate::api::{
     v1::decode_rows_to_table,
 };
 
-#[derive(FromRow)]
+#[derive(FromRow, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LogInAttempts {
     pub ip: IpAddr,
     pub user_id: i32,
@@ -32,6 +33,19 @@ impl LogInAttempts {
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
     }
 
+    pub async fn get_by_user_id(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as::<_, Self>(
+            "SELECT * FROM ip_request_throttles WHERE user_id = $1 ORDER BY timestamp DESC",
+        )
+        .bind(user_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
     pub fn is_on_cooldown(mut data: Vec<Self>, ip: IpAddr, user_id: i32) -> bool {
         if data.len() < 5 {
             return false;
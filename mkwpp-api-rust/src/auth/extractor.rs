@@ -0,0 +1,157 @@
+use std::{future::Future, pin::Pin};
+
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest,
+    cookie::{Cookie, SameSite},
+    dev::Payload,
+    http::header::AUTHORIZATION,
+};
+
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    auth::roles::Role,
+};
+
+pub const SESSION_COOKIE_NAME: &str = "session_token";
+
+/// A user authenticated through either the `Authorization: Bearer` header
+/// or the session cookie set on login
+#[derive(sqlx::FromRow, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+    pub player_id: Option<i32>,
+    pub username: String,
+    pub session_token: String,
+    pub roles: Vec<Role>,
+}
+
+/// A user who passed the role guard of the admin scope handling the request,
+/// see `api::v1::admin::require_role`. Outside of a guarded scope it rejects everyone
+#[derive(Clone)]
+pub struct AdminUser {
+    pub user: AuthenticatedUser,
+    /// The role the scope requires
+    pub role: Role,
+}
+
+impl std::ops::Deref for AdminUser {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+pub fn get_session_token(req: &HttpRequest) -> Option<String> {
+    if let Some(token) = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+
+    req.cookie(SESSION_COOKIE_NAME)
+        .map(|x| x.value().to_string())
+}
+
+impl AuthenticatedUser {
+    pub fn has_role(&self, role: Role) -> bool {
+        role.is_granted_by(&self.roles)
+    }
+
+    pub async fn from_session_token(
+        session_token: &str,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Self, FinalErrorResponse> {
+        let user_id = super::sessions::Sessions::touch(executor, session_token, None)
+            .await?
+            .ok_or(EveryReturnedError::InvalidSessionToken.into_final_error(""))?;
+
+        sqlx::query_as::<_, Self>(
+            r#"
+                SELECT
+                    users.id AS user_id,
+                    users.player_id,
+                    users.username,
+                    $2::TEXT AS session_token,
+                    ARRAY(
+                        SELECT role
+                        FROM user_roles
+                        WHERE user_roles.user_id = users.id
+                    ) AS roles
+                FROM users
+                WHERE users.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(session_token)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidSessionToken.into_final_error(""))
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = FinalErrorResponse;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let session_token = get_session_token(req);
+        Box::pin(async move {
+            let session_token = session_token
+                .ok_or(EveryReturnedError::InvalidSessionToken.into_final_error(""))?;
+
+            let app_state = crate::app_state::access_app_state().await;
+            let mut executor = {
+                let app_state = app_state.read().await;
+                app_state.acquire_pg_connection().await?
+            };
+
+            let user = Self::from_session_token(&session_token, &mut executor).await?;
+
+            crate::api::v1::close_connection(executor).await?;
+
+            Ok(user)
+        })
+    }
+}
+
+impl FromRequest for AdminUser {
+    type Error = FinalErrorResponse;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        std::future::ready(
+            req.extensions()
+                .get::<AdminUser>()
+                .filter(|x| x.has_role(x.role))
+                .cloned()
+                .ok_or(EveryReturnedError::InsufficientPermissions.into_final_error("")),
+        )
+    }
+}
+
+pub fn session_cookie(log_in_data: &super::LogInData) -> Cookie<'static> {
+    let mut cookie = Cookie::build(SESSION_COOKIE_NAME, log_in_data.session_token.clone())
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .finish();
+    cookie.set_expires(
+        actix_web::cookie::time::OffsetDateTime::from_unix_timestamp(
+            log_in_data.expiry.timestamp(),
+        )
+        .ok(),
+    );
+    cookie
+}
+
+pub fn removal_session_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(SESSION_COOKIE_NAME, "").path("/").finish();
+    cookie.make_removal();
+    cookie
+}
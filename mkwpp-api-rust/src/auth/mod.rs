@@ -15,6 +15,11 @@ use crate::{
 };
 
 mod cooldown;
+pub mod extractor;
+pub mod personal_data;
+pub mod roles;
+pub mod sessions;
+pub mod two_factor;
 pub mod validated_strings;
 
 #[derive(serde::Serialize, sqlx::FromRow)]
@@ -50,25 +55,20 @@ impl Users {
         player_id: Option<i32>,
         executor: &mut sqlx::PgConnection,
     ) -> Result<sqlx::postgres::PgQueryResult, FinalErrorResponse> {
-        let hash_and_salt = password.map(|x| {
-            let salt = argon2::password_hash::SaltString::generate(
-                &mut argon2::password_hash::rand_core::OsRng,
-            );
-            (x.hash(salt.as_str().as_bytes()), salt)
-        });
-
-        match (id, hash_and_salt) {
+        let hash = password.map(|x| x.hash());
+
+        match (id, hash) {
             (None, None) => {
                 sqlx::query(const_format::formatcp!("INSERT INTO {table_name} (username, email, is_superuser, is_staff, is_active, is_verified, player_id) VALUES ($1, $2, $3, $4, $5, $6, $7);", table_name = Users::TABLE_NAME))
             }
             (Some(id), None) => {
                 sqlx::query(const_format::formatcp!("UPDATE {table_name} SET (username, email, is_superuser, is_staff, is_active, is_verified, player_id) = ($2, $3, $4, $5, $6, $7, $8) WHERE id = $1;", table_name = Users::TABLE_NAME)).bind(id)
             }
-            (None, Some((hash, salt))) => {
-                sqlx::query(const_format::formatcp!("INSERT INTO {table_name} (password, salt, username, email, is_superuser, is_staff, is_active, is_verified, player_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9);", table_name = Users::TABLE_NAME)).bind(hash).bind(salt.as_str().to_owned())
+            (None, Some(hash)) => {
+                sqlx::query(const_format::formatcp!("INSERT INTO {table_name} (password, username, email, is_superuser, is_staff, is_active, is_verified, player_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8);", table_name = Users::TABLE_NAME)).bind(hash)
             }
-            (Some(id), Some((hash, salt))) => {
-                sqlx::query(const_format::formatcp!("UPDATE {table_name} SET (password, salt, username, email, is_superuser, is_staff, is_active, is_verified, player_id) = ($2, $3, $4, $5, $6, $7, $8, $9, $10) WHERE id = $1;", table_name = Users::TABLE_NAME)).bind(id).bind(hash).bind(salt.as_str().to_owned())
+            (Some(id), Some(hash)) => {
+                sqlx::query(const_format::formatcp!("UPDATE {table_name} SET (password, salt, username, email, is_superuser, is_staff, is_active, is_verified, player_id) = ($2, NULL, $3, $4, $5, $6, $7, $8, $9) WHERE id = $1;", table_name = Users::TABLE_NAME)).bind(id).bind(hash)
             }
         }
         .bind(username.get_inner())
@@ -82,25 +82,36 @@ impl Users {
     }
 }
 
-pub async fn get_username_by_id(
-    id: i32,
-    executor: &mut sqlx::PgConnection,
-) -> Result<String, FinalErrorResponse> {
-    sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
-        .bind(id)
-        .fetch_one(executor)
-        .await
-        .map_err(|e| EveryReturnedError::UserIDDoesntExist.into_final_error(e))
-}
-
 #[derive(sqlx::FromRow)]
 struct BareMinimumData {
     id: i32,
     password: String,
-    salt: String,
+    salt: Option<String>,
     is_verified: bool,
 }
 
+/// Hashes in the old format, or made with outdated parameters,
+/// get replaced with a fresh hash once the password is known to be correct
+async fn verify_password(
+    data: &BareMinimumData,
+    password: &validated_strings::password::Password,
+    executor: &mut sqlx::PgConnection,
+) -> Result<bool, FinalErrorResponse> {
+    match password.verify(&data.password, data.salt.as_deref()) {
+        validated_strings::password::PasswordMatch::Mismatch => Ok(false),
+        validated_strings::password::PasswordMatch::Match => Ok(true),
+        validated_strings::password::PasswordMatch::NeedsRehash => {
+            sqlx::query("UPDATE users SET password = $1, salt = NULL WHERE id = $2")
+                .bind(password.hash())
+                .bind(data.id)
+                .execute(executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+            Ok(true)
+        }
+    }
+}
+
 #[derive(serde::Serialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct LogInData {
@@ -112,9 +123,10 @@ pub async fn login(
     username: validated_strings::username::Username,
     password: validated_strings::password::Password,
     ip: IpAddr,
+    user_agent: Option<String>,
     // should be transaction
     executor: &mut sqlx::PgConnection,
-) -> Result<LogInData, FinalErrorResponse> {
+) -> Result<two_factor::LogInResult, FinalErrorResponse> {
     let data = sqlx::query_as::<_, BareMinimumData>(const_format::formatc!(
         r#"
         SELECT
@@ -142,51 +154,48 @@ pub async fn login(
         return Err(EveryReturnedError::UserNotVerified.into_final_error(""));
     };
 
-    let hash = password.hash(data.salt.as_bytes());
-
-    match hash == data.password {
+    match verify_password(&data, &password, executor).await? {
         false => {
             cooldown::LogInAttempts::insert(executor, ip, data.id).await?;
             Err(EveryReturnedError::InvalidInput.into_final_error(""))
         }
         true => {
-            let token_engine = base64::engine::GeneralPurpose::new(
-                &base64::alphabet::URL_SAFE,
-                base64::engine::GeneralPurposeConfig::new(),
-            );
-
-            let mut hash_bytes = [0u8; 96];
-            let mut out_string = String::new();
-            rand::rng().fill(&mut hash_bytes);
-            token_engine.encode_string(hash_bytes, &mut out_string);
-
-            let log_in_data = sqlx::query_as::<_, LogInData>(const_format::formatc!(
-                r#"
-                    INSERT INTO auth_tokens (user_id, session_token)
-                    VALUES ($1, $2)
-                    RETURNING session_token, expiry
-                "#
+            let (totp_enabled, totp_required) = two_factor::get_status(data.id, executor).await?;
+            if totp_enabled || totp_required {
+                return Ok(two_factor::LogInResult::Challenge(
+                    two_factor::create_challenge(data.id, !totp_enabled, executor).await?,
+                ));
+            }
+
+            Ok(two_factor::LogInResult::Session(
+                sessions::Sessions::insert(executor, data.id, ip, user_agent).await?,
             ))
-            .bind(data.id)
-            .bind(out_string)
-            .fetch_one(&mut *executor)
-            .await
-            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
-            Ok(log_in_data)
         }
     }
 }
 
+fn generate_session_token() -> String {
+    let token_engine = base64::engine::GeneralPurpose::new(
+        &base64::alphabet::URL_SAFE,
+        base64::engine::GeneralPurposeConfig::new(),
+    );
+
+    let mut hash_bytes = [0u8; 96];
+    let mut out_string = String::new();
+    rand::rng().fill(&mut hash_bytes);
+    token_engine.encode_string(hash_bytes, &mut out_string);
+    out_string
+}
+
 pub async fn register(
     username: validated_strings::username::Username,
     password: validated_strings::password::Password,
     email: validated_strings::email::Email,
+    locale: &str,
     // This should be a transaction!
     executor: &mut sqlx::PgConnection,
 ) -> Result<(), FinalErrorResponse> {
-    let salt =
-        argon2::password_hash::SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
-    let hash_string = password.hash(salt.as_str().as_bytes());
+    let hash_string = password.hash();
 
     let email = email.get_inner();
     let username = username.get_inner();
@@ -203,7 +212,7 @@ pub async fn register(
 
     let user_id: i32 = sqlx::query_scalar(const_format::formatc!(
         r#"
-            INSERT INTO users (username, password, email, salt, is_active)
+            INSERT INTO users (username, password, email, locale, is_active)
             VALUES($1, $2, $3, $4, true)
             RETURNING id
         "#
@@ -211,7 +220,7 @@ pub async fn register(
     .bind(username.as_str())
     .bind(hash_string)
     .bind(email.as_str())
-    .bind(salt.as_str())
+    .bind(locale)
     .fetch_one(&mut *executor)
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
@@ -228,7 +237,14 @@ pub async fn register(
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
 
-    crate::mail::MailService::account_verification(&username, &email, &out_string).await?;
+    crate::mail::MailService::account_verification(
+        executor,
+        &username,
+        &email,
+        locale,
+        &out_string,
+    )
+    .await?;
 
     Ok(())
 }
@@ -252,7 +268,7 @@ pub async fn password_reset_token_gen(
 
     let user = sqlx::query(
         r#"
-            SELECT id, username FROM users WHERE email = $1
+            SELECT id, username, locale FROM users WHERE email = $1
         "#,
     )
     .bind(email.as_str())
@@ -280,7 +296,10 @@ pub async fn password_reset_token_gen(
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
 
-    crate::mail::MailService::password_reset(&username, &email, &out_string).await?;
+    let locale = user.get::<String, &str>("locale");
+
+    crate::mail::MailService::password_reset(executor, &username, &email, &locale, &out_string)
+        .await?;
 
     Ok(())
 }
@@ -290,27 +309,27 @@ pub async fn reset_password(
     password: validated_strings::password::Password,
     executor: &mut sqlx::PgConnection,
 ) -> Result<(), FinalErrorResponse> {
-    let salt =
-        argon2::password_hash::SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
-    let hash_string = password.hash(salt.as_str().as_bytes());
-
-    sqlx::query(
+    let result = sqlx::query(
         r#"
-            UPDATE users AS u1
-            SET password = $2, salt = $3
-            FROM users AS u2
-            LEFT JOIN tokens AS t
-                ON t.user_id = u2.id
-            WHERE t.token = $1 AND token_type = 'password_reset'::token_type
+            UPDATE users
+            SET password = $2, salt = NULL
+            FROM tokens
+            WHERE
+                tokens.user_id = users.id AND
+                tokens.token = $1 AND
+                tokens.token_type = 'password_reset'::token_type
         "#,
     )
     .bind(token)
-    .bind(hash_string)
-    .bind(salt.to_string())
+    .bind(password.hash())
     .execute(&mut *executor)
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
 
+    if result.rows_affected() == 0 {
+        return Err(EveryReturnedError::InvalidInput.into_final_error(""));
+    }
+
     sqlx::query(
         "DELETE FROM tokens WHERE token = $1 AND token_type = 'password_reset'::token_type",
     )
@@ -345,21 +364,28 @@ pub async fn activate_account(
     activation_token: &str,
     executor: &mut sqlx::PgConnection,
 ) -> Result<(), FinalErrorResponse> {
-    sqlx::query(
+    let result = sqlx::query(
         r#"
-            UPDATE users AS u1
+            UPDATE users
             SET is_verified = true
-            FROM users AS u2
-            LEFT JOIN tokens AS t
-                ON t.user_id = u2.id
-            WHERE t.token = $1 AND token_type = 'activation'::token_type
+            FROM tokens AS t
+            WHERE
+                t.user_id = users.id AND
+                t.token = $1 AND
+                t.token_type = 'activation'::token_type AND
+                t.time >= NOW() - make_interval(hours => $2)
         "#,
     )
     .bind(activation_token)
+    .bind(crate::ENV_VARS.activation_token_expiry as i32)
     .execute(&mut *executor)
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
 
+    if result.rows_affected() == 0 {
+        return Err(EveryReturnedError::InvalidInput.into_final_error(""));
+    }
+
     sqlx::query("DELETE FROM tokens WHERE token = $1 AND token_type = 'activation'::token_type")
         .bind(activation_token)
         .execute(&mut *executor)
@@ -369,6 +395,125 @@ pub async fn activate_account(
     Ok(())
 }
 
+/// Sends a new activation email, unknown or already verified emails are silently ignored
+pub async fn resend_activation(
+    email: validated_strings::email::Email,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let email = email.get_inner();
+
+    let user = sqlx::query(
+        r#"
+            SELECT
+                id, username, locale,
+                EXISTS(
+                    SELECT 1
+                    FROM tokens
+                    WHERE
+                        tokens.user_id = users.id AND
+                        tokens.token_type = 'activation'::token_type AND
+                        tokens.time >= NOW() - make_interval(secs => $2)
+                ) AS is_on_cooldown
+            FROM users
+            WHERE email = $1 AND is_verified = false
+        "#,
+    )
+    .bind(email.as_str())
+    .bind(crate::ENV_VARS.activation_resend_cooldown as f64)
+    .fetch_optional(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    let user = match user {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    if user.get::<bool, &str>("is_on_cooldown") {
+        return Err(EveryReturnedError::UserOnCooldown.into_final_error(""));
+    }
+
+    let id = user.get::<i32, &str>("id");
+    let username = user.get::<String, &str>("username");
+
+    let token_engine = base64::engine::GeneralPurpose::new(
+        &base64::alphabet::URL_SAFE,
+        base64::engine::GeneralPurposeConfig::new(),
+    );
+
+    let mut hash_bytes = [0u8; 45];
+    let mut out_string = String::new();
+    rand::rng().fill(&mut hash_bytes);
+    token_engine.encode_string(hash_bytes, &mut out_string);
+
+    sqlx::query("DELETE FROM tokens WHERE user_id = $1 AND token_type = 'activation'::token_type")
+        .bind(id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query(const_format::formatc!(
+        r#"
+            INSERT INTO tokens (token, user_id, token_type)
+            VALUES($1, $2, 'activation'::token_type)
+        "#
+    ))
+    .bind(&out_string)
+    .bind(id)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    let locale = user.get::<String, &str>("locale");
+
+    crate::mail::MailService::account_verification(
+        executor,
+        &username,
+        &email,
+        &locale,
+        &out_string,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes accounts that were never activated, along with everything that references them
+pub async fn cleanup_unverified_accounts(
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let user_ids: Vec<i32> = sqlx::query_scalar(
+        r#"
+            SELECT id
+            FROM users
+            WHERE
+                is_verified = false AND
+                player_id IS NULL AND
+                created < NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(crate::ENV_VARS.unverified_account_expiry as i32)
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    for table_name in ["tokens", "auth_tokens", "ip_request_throttles"] {
+        sqlx::query(&format!("DELETE FROM {table_name} WHERE user_id = ANY($1)"))
+            .bind(&user_ids)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    }
+
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+        .bind(&user_ids)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
 pub async fn update_password(
     id: i32,
     old_password: validated_strings::password::Password,
@@ -389,21 +534,16 @@ pub async fn update_password(
     .await
     .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
 
-    if old_password.hash(data.salt.as_bytes()) != data.password {
+    if !verify_password(&data, &old_password, executor).await? {
         return Err(EveryReturnedError::InvalidInput.into_final_error(""));
     };
 
-    let salt =
-        argon2::password_hash::SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
-    let hash_string = new_password.hash(salt.as_str().as_bytes());
-
     sqlx::query(const_format::formatc!(
         r#"
-            UPDATE users SET password = $1, salt = $2 WHERE id = $3
+            UPDATE users SET password = $1, salt = NULL WHERE id = $2
         "#
     ))
-    .bind(hash_string)
-    .bind(salt.as_str())
+    .bind(new_password.hash())
     .bind(id)
     .execute(executor)
     .await
@@ -412,6 +552,156 @@ pub async fn update_password(
     Ok(())
 }
 
+/// Wrong passwords count towards the login cooldown
+pub async fn change_email_token_gen(
+    id: i32,
+    password: validated_strings::password::Password,
+    new_email: validated_strings::email::Email,
+    ip: IpAddr,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let data = sqlx::query_as::<_, BareMinimumData>(const_format::formatc!(
+        r#"
+        SELECT
+            id, password, salt, is_verified
+        FROM users
+        WHERE id = $1
+    "#
+    ))
+    .bind(id)
+    .fetch_one(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    if cooldown::LogInAttempts::is_on_cooldown(
+        cooldown::LogInAttempts::get_from_sql(executor, ip, id).await?,
+        ip,
+        id,
+    ) {
+        return Err(EveryReturnedError::UserOnCooldown.into_final_error(""));
+    }
+
+    if !verify_password(&data, &password, executor).await? {
+        cooldown::LogInAttempts::insert(executor, ip, id).await?;
+        return Err(EveryReturnedError::InvalidInput.into_final_error(""));
+    };
+
+    let new_email = new_email.get_inner();
+
+    let is_email_taken: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+            .bind(&new_email)
+            .fetch_one(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    if is_email_taken {
+        return Err(EveryReturnedError::InvalidInput.into_final_error("Email is already in use"));
+    }
+
+    let user = sqlx::query("SELECT username, email, locale FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    let username = user.get::<String, &str>("username");
+    let old_email = user.get::<String, &str>("email");
+    let locale = user.get::<String, &str>("locale");
+
+    let token_engine = base64::engine::GeneralPurpose::new(
+        &base64::alphabet::URL_SAFE,
+        base64::engine::GeneralPurposeConfig::new(),
+    );
+
+    let mut hash_bytes = [0u8; 45];
+    let mut out_string = String::new();
+    rand::rng().fill(&mut hash_bytes);
+    token_engine.encode_string(hash_bytes, &mut out_string);
+
+    sqlx::query(
+        "DELETE FROM tokens WHERE user_id = $1 AND token_type = 'email_change'::token_type",
+    )
+    .bind(id)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query(const_format::formatc!(
+        r#"
+            INSERT INTO tokens (token, user_id, token_type, new_email)
+            VALUES($1, $2, 'email_change'::token_type, $3)
+        "#
+    ))
+    .bind(&out_string)
+    .bind(id)
+    .bind(&new_email)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    crate::mail::MailService::email_change_confirmation(
+        &mut *executor,
+        &username,
+        &new_email,
+        &locale,
+        &out_string,
+    )
+    .await?;
+    crate::mail::MailService::email_change_notice(
+        executor, &username, &old_email, &locale, &new_email,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// The new email is checked again, it could have been taken since the token was sent
+pub async fn change_email(
+    token: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let (user_id, new_email): (i32, String) = sqlx::query_as(
+        r#"
+            SELECT user_id, new_email FROM tokens
+            WHERE
+                token = $1 AND
+                token_type = 'email_change'::token_type AND
+                new_email IS NOT NULL AND
+                time >= NOW() - INTERVAL '24 hours'
+        "#,
+    )
+    .bind(token)
+    .fetch_optional(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+    .ok_or(EveryReturnedError::InvalidInput.into_final_error(""))?;
+
+    let is_email_taken: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND id != $2)")
+            .bind(&new_email)
+            .bind(user_id)
+            .fetch_one(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    if is_email_taken {
+        return Err(EveryReturnedError::InvalidInput.into_final_error("Email is already in use"));
+    }
+
+    sqlx::query("UPDATE users SET email = $1 WHERE id = $2")
+        .bind(&new_email)
+        .bind(user_id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query("DELETE FROM tokens WHERE token = $1 AND token_type = 'email_change'::token_type")
+        .bind(token)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BareMinimumValidationData {
@@ -424,39 +714,18 @@ pub async fn is_valid_token(
     user_id: i32,
     executor: &mut sqlx::PgConnection,
 ) -> Result<bool, FinalErrorResponse> {
-    sqlx::query(
-        r#"
-            SELECT user_id
-            FROM auth_tokens
-            WHERE
-                session_token = $1 AND
-                user_id = $2 AND
-                expiry >= NOW()
-        "#,
-    )
-    .bind(session_token)
-    .bind(user_id)
-    .fetch_optional(executor)
-    .await
-    .map(|x| x.is_some())
-    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    sessions::Sessions::touch(executor, session_token, Some(user_id))
+        .await
+        .map(|x| x.is_some())
 }
 
-pub async fn is_user_admin(
+/// Whether the user holds the given role, superusers hold every role
+pub async fn user_has_role(
     user_id: i32,
+    role: roles::Role,
     executor: &mut sqlx::PgConnection,
 ) -> Result<bool, FinalErrorResponse> {
-    return sqlx::query_scalar(const_format::formatc!(
-        r#"
-            SELECT is_staff
-            FROM users
-            WHERE id = $1
-        "#
-    ))
-    .bind(user_id)
-    .fetch_one(executor)
-    .await
-    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    Ok(role.is_granted_by(&roles::Role::get_user_roles(user_id, executor).await?))
 }
 
 #[derive(sqlx::FromRow, Deserialize, Serialize)]
@@ -471,6 +740,10 @@ pub async fn get_user_data(
     session_token: &str,
     executor: &mut sqlx::PgConnection,
 ) -> Result<ClientSideUserData, FinalErrorResponse> {
+    let user_id = sessions::Sessions::touch(executor, session_token, None)
+        .await?
+        .ok_or(EveryReturnedError::UserIDDoesntExist.into_final_error(""))?;
+
     decode_row_to_table(
         sqlx::query(const_format::formatc!(
             r#"
@@ -479,14 +752,10 @@ pub async fn get_user_data(
                 users.id AS user_id,
                 username
             FROM users
-            LEFT JOIN auth_tokens
-                ON auth_tokens.user_id = users.id
-            WHERE
-                session_token = $1 AND
-                expiry >= NOW()
+            WHERE id = $1
         "#
         ))
-        .bind(session_token)
+        .bind(user_id)
         .fetch_optional(executor)
         .await
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
@@ -514,6 +783,25 @@ pub async fn get_user_id_from_player_id(
     .ok_or(EveryReturnedError::UserHasNoAssociatedPlayer.into_final_error(""))
 }
 
+pub async fn set_locale(
+    user_id: i32,
+    locale: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    if !crate::mail::templates::is_valid_locale(locale) {
+        return Err(EveryReturnedError::InvalidInput.into_final_error("Invalid locale"));
+    }
+
+    sqlx::query("UPDATE users SET locale = $1 WHERE id = $2")
+        .bind(locale)
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
 pub async fn logout(
     session_token: &str,
     executor: &mut sqlx::PgConnection,
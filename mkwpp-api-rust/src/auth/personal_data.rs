@@ -0,0 +1,220 @@
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    auth::{
+        BareMinimumData, cooldown::LogInAttempts, roles::Role, sessions::Sessions,
+        validated_strings, verify_password,
+    },
+    custom_serde::DateAsTimestampNumber,
+    mail::notifications::NotificationPreferences,
+    sql::tables::{
+        comments::Comments,
+        players::{Players, claims::PlayerClaims},
+        submissions::{Submissions, edit_submissions::EditSubmissions},
+    },
+};
+
+#[derive(serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountData {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub locale: String,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub is_active: bool,
+    pub is_verified: bool,
+    pub player_id: Option<i32>,
+    pub totp_enabled: bool,
+    #[sqlx(skip)]
+    pub roles: Vec<Role>,
+}
+
+/// Everything stored about a user, as returned by `/auth/export`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalDataExport {
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub account: AccountData,
+    pub player: Option<Players>,
+    pub player_claims: Vec<PlayerClaims>,
+    pub submissions: Vec<Submissions>,
+    pub edit_submissions: Vec<EditSubmissions>,
+    pub login_attempts: Vec<LogInAttempts>,
+    pub sessions: Vec<Sessions>,
+    pub notification_preferences: NotificationPreferences,
+    pub comments: Vec<Comments>,
+}
+
+pub async fn export(
+    user_id: i32,
+    current_session_token: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<PersonalDataExport, FinalErrorResponse> {
+    let mut account = sqlx::query_as::<_, AccountData>(
+        r#"
+            SELECT
+                id, username, email, locale, created, is_active,
+                is_verified, player_id, totp_enabled
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+    .ok_or(EveryReturnedError::UserIDDoesntExist.into_final_error(""))?;
+    account.roles = Role::get_user_roles(user_id, executor).await?;
+
+    let player = sqlx::query_as::<_, Players>("SELECT * FROM players WHERE id = $1")
+        .bind(account.player_id)
+        .fetch_optional(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    let submissions = sqlx::query_as::<_, Submissions>(
+        "SELECT * FROM submissions WHERE submitter_id = $1 OR player_id = $2 ORDER BY submitted_at",
+    )
+    .bind(user_id)
+    .bind(account.player_id)
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    let edit_submissions = sqlx::query_as::<_, EditSubmissions>(
+        r#"
+            SELECT edit_submissions.*
+            FROM edit_submissions
+            LEFT JOIN scores ON scores.id = edit_submissions.score_id
+            WHERE
+                edit_submissions.submitter_id = $1 OR
+                scores.player_id = $2
+            ORDER BY edit_submissions.submitted_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(account.player_id)
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(PersonalDataExport {
+        generated_at: chrono::Utc::now(),
+        player_claims: PlayerClaims::get_by_user_id(executor, user_id).await?,
+        login_attempts: LogInAttempts::get_by_user_id(executor, user_id).await?,
+        sessions: Sessions::get_by_user_id(executor, user_id, current_session_token).await?,
+        notification_preferences: NotificationPreferences::get(executor, user_id).await?,
+        comments: Comments::get_by_user_id(executor, user_id).await?,
+        account,
+        player,
+        submissions,
+        edit_submissions,
+    })
+}
+
+/// Anonymises the account instead of deleting the row, since submissions,
+/// blog posts and comments keep referencing it. The player profile and its scores
+/// are left untouched, but get unlinked from the account
+pub async fn delete_account(
+    user_id: i32,
+    password: validated_strings::password::Password,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let data = sqlx::query_as::<_, BareMinimumData>(
+        r#"
+            SELECT
+                id, password, salt, is_verified
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    if !verify_password(&data, &password, executor).await? {
+        return Err(EveryReturnedError::InvalidInput.into_final_error(""));
+    };
+
+    for table_name in [
+        "tokens",
+        "auth_tokens",
+        "ip_request_throttles",
+        "totp_recovery_codes",
+        "user_roles",
+        "player_claims",
+        "notification_preferences",
+    ] {
+        sqlx::query(&format!("DELETE FROM {table_name} WHERE user_id = $1"))
+            .bind(user_id)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    }
+
+    // Comments keep their place in threads, but lose their text
+    sqlx::query(
+        r#"
+            UPDATE comments
+            SET markdown = '', content = '', deleted_at = COALESCE(deleted_at, NOW())
+            WHERE author_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    // Queued and sent emails hold the address, and their bodies may hold tokens
+    sqlx::query(
+        r#"
+            DELETE FROM email_outbox
+            USING users
+            WHERE
+                users.id = $1 AND
+                (
+                    email_outbox.recipient_email = users.email OR
+                    email_outbox.recipient_name = users.username
+                )
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query("UPDATE players SET submitters = array_remove(submitters, $1)")
+        .bind(user_id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    // An empty password is neither a valid PHC string nor an old hash, so nothing can match it
+    sqlx::query(
+        r#"
+            UPDATE users
+            SET
+                username = 'deleted_user_' || id,
+                email = 'deleted_user_' || id || '@invalid',
+                password = '',
+                salt = NULL,
+                is_superuser = false,
+                is_staff = false,
+                is_active = false,
+                player_id = NULL,
+                totp_secret = NULL,
+                totp_enabled = false,
+                totp_last_counter = NULL
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
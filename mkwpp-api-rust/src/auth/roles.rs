@@ -0,0 +1,55 @@
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+/// Superusers implicitly hold every other role
+#[derive(sqlx::Type, serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "user_role", rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    Superuser,
+    SubmissionReviewer,
+    ScoreEditor,
+    PlayerEditor,
+    BlogAuthor,
+    StandardsEditor,
+    CommentModerator,
+}
+
+impl Role {
+    pub fn is_granted_by(self, roles: &[Role]) -> bool {
+        roles.contains(&Role::Superuser) || roles.contains(&self)
+    }
+
+    pub async fn get_user_roles(
+        user_id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<Role>, FinalErrorResponse> {
+        sqlx::query_scalar("SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role")
+            .bind(user_id)
+            .fetch_all(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn set_user_roles(
+        user_id: i32,
+        roles: &[Role],
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<(), FinalErrorResponse> {
+        sqlx::query("DELETE FROM user_roles WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, role) SELECT $1, UNNEST($2::user_role[]) ON CONFLICT DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(roles)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use sqlx::{FromRow, postgres::PgQueryResult};
+use std::net::IpAddr;
+
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+/// How stale `last_used` may get before validating the token refreshes it
+const TOUCH_INTERVAL_MINUTES: i32 = 5;
+
+/// Where the request being handled comes from
+#[derive(Clone)]
+struct Client {
+    ip: Option<IpAddr>,
+    user_agent: Option<String>,
+}
+
+tokio::task_local! {
+    static CLIENT: Client;
+}
+
+/// Middleware remembering the IP and user agent of each request,
+/// so that validating a session token can refresh them through [`Sessions::touch`]
+pub async fn track_client<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, actix_web::Error> {
+    let client = Client {
+        ip: req.peer_addr().map(|x| x.ip()),
+        user_agent: req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|x| x.to_str().ok())
+            .map(String::from),
+    };
+    CLIENT.scope(client, next.call(req)).await
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(FromRow, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sessions {
+    pub id: i32,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub last_used: chrono::DateTime<chrono::Utc>,
+    pub expiry: chrono::DateTime<chrono::Utc>,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub is_current: bool,
+}
+
+impl Sessions {
+    pub async fn insert(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        ip: IpAddr,
+        user_agent: Option<String>,
+    ) -> Result<super::LogInData, FinalErrorResponse> {
+        sqlx::query_as::<_, super::LogInData>(
+            r#"
+                INSERT INTO auth_tokens (user_id, session_token, ip, user_agent)
+                VALUES ($1, $2, $3, $4)
+                RETURNING session_token, expiry
+            "#,
+        )
+        .bind(user_id)
+        .bind(super::generate_session_token())
+        .bind(ip)
+        .bind(user_agent)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn get_by_user_id(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        current_session_token: &str,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as::<_, Self>(
+            r#"
+                SELECT
+                    id, created, last_used, expiry, ip, user_agent,
+                    session_token = $2 AS is_current
+                FROM auth_tokens
+                WHERE
+                    user_id = $1 AND
+                    expiry >= NOW()
+                ORDER BY last_used DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(current_session_token)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Marks the session as used just now, from the IP and user agent of the request
+    /// being handled. Returns the user the session belongs to, if it is still valid.
+    /// Passing a user id only touches the session if it belongs to that user.
+    /// The session is written to at most once every `TOUCH_INTERVAL_MINUTES`
+    pub async fn touch(
+        executor: &mut sqlx::PgConnection,
+        session_token: &str,
+        user_id: Option<i32>,
+    ) -> Result<Option<i32>, FinalErrorResponse> {
+        let client = CLIENT.try_with(Client::clone).ok();
+
+        sqlx::query_scalar(
+            r#"
+                WITH session AS (
+                    SELECT id, user_id, last_used FROM auth_tokens
+                    WHERE
+                        session_token = $1 AND
+                        ($2::INTEGER IS NULL OR user_id = $2) AND
+                        expiry >= NOW()
+                ), touched AS (
+                    UPDATE auth_tokens
+                    SET
+                        last_used = NOW(),
+                        ip = COALESCE($3, auth_tokens.ip),
+                        user_agent = COALESCE($4, auth_tokens.user_agent)
+                    FROM session
+                    WHERE
+                        auth_tokens.id = session.id AND
+                        session.last_used < NOW() - make_interval(mins => $5)
+                )
+                SELECT user_id FROM session
+            "#,
+        )
+        .bind(session_token)
+        .bind(user_id)
+        .bind(client.as_ref().and_then(|x| x.ip))
+        .bind(client.and_then(|x| x.user_agent))
+        .bind(TOUCH_INTERVAL_MINUTES)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Replaces the session token with a freshly generated one and extends its expiry,
+    /// the old token stops working immediately
+    pub async fn rotate(
+        executor: &mut sqlx::PgConnection,
+        session_token: &str,
+        ip: IpAddr,
+        user_agent: Option<String>,
+    ) -> Result<super::LogInData, FinalErrorResponse> {
+        sqlx::query_as::<_, super::LogInData>(
+            r#"
+                UPDATE auth_tokens
+                SET
+                    session_token = $2,
+                    expiry = NOW() + INTERVAL '1 month',
+                    last_used = NOW(),
+                    ip = $3,
+                    user_agent = COALESCE($4, user_agent)
+                WHERE
+                    session_token = $1 AND
+                    expiry >= NOW()
+                RETURNING session_token, expiry
+            "#,
+        )
+        .bind(session_token)
+        .bind(super::generate_session_token())
+        .bind(ip)
+        .bind(user_agent)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidSessionToken.into_final_error(""))
+    }
+
+    pub async fn revoke(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        session_id: i32,
+    ) -> Result<PgQueryResult, FinalErrorResponse> {
+        sqlx::query("DELETE FROM auth_tokens WHERE user_id = $1 AND id = $2")
+            .bind(user_id)
+            .bind(session_id)
+            .execute(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Revokes every session of the user, optionally keeping the one in use
+    pub async fn revoke_all(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        keep_session_token: Option<&str>,
+    ) -> Result<PgQueryResult, FinalErrorResponse> {
+        sqlx::query(
+            r#"
+                DELETE FROM auth_tokens
+                WHERE
+                    user_id = $1 AND
+                    ($2::VARCHAR IS NULL OR session_token != $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(keep_session_token)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+}
@@ -0,0 +1,456 @@
+use std::net::IpAddr;
+
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Digest;
+
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+use super::{LogInData, cooldown::LogInAttempts, sessions::Sessions};
+
+const TOTP_ISSUER: &str = "MKWPP";
+const TOTP_STEP_SECONDS: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// Amount of steps before and after the current one that are still accepted
+const TOTP_ALLOWED_DRIFT: i64 = 1;
+const TOTP_SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 10;
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub enum LogInResult {
+    Session(LogInData),
+    Challenge(TwoFactorChallenge),
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorChallenge {
+    pub challenge_token: String,
+    /// The user has to enrol in TOTP before the challenge can be completed
+    pub enrolment_required: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrolment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct TotpData {
+    totp_secret: Option<String>,
+    totp_enabled: bool,
+    totp_last_counter: Option<i64>,
+}
+
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let result = mac.finalize().into_bytes();
+
+    let offset = (result[result.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes([
+        result[offset] & 0x7f,
+        result[offset + 1],
+        result[offset + 2],
+        result[offset + 3],
+    ]);
+    code % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Returns the time step the code was generated for, if it's valid
+fn find_totp_counter(secret: &str, code: &str) -> Option<i64> {
+    find_totp_counter_at(secret, code, chrono::Utc::now().timestamp())
+}
+
+fn find_totp_counter_at(secret: &str, code: &str, timestamp: i64) -> Option<i64> {
+    let secret = data_encoding::BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let code = code.trim().parse::<u32>().ok()?;
+    let current_counter = timestamp / TOTP_STEP_SECONDS;
+
+    (current_counter - TOTP_ALLOWED_DRIFT..=current_counter + TOTP_ALLOWED_DRIFT)
+        .find(|counter| hotp(&secret, *counter as u64) == code)
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|x| x.is_ascii_alphanumeric())
+        .map(|x| x.to_ascii_uppercase())
+        .collect();
+    data_encoding::HEXLOWER.encode(&sha2::Sha256::digest(normalized.as_bytes()))
+}
+
+fn generate_base32<const N: usize>() -> String {
+    let mut bytes = [0u8; N];
+    rand::rng().fill(&mut bytes);
+    data_encoding::BASE32_NOPAD.encode(&bytes)
+}
+
+async fn get_totp_data(
+    user_id: i32,
+    executor: &mut sqlx::PgConnection,
+) -> Result<TotpData, FinalErrorResponse> {
+    sqlx::query_as::<_, TotpData>(
+        "SELECT totp_secret, totp_enabled, totp_last_counter FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+}
+
+/// Anyone holding a role, whichever it is, has to use two-factor authentication
+async fn is_required(
+    user_id: i32,
+    executor: &mut sqlx::PgConnection,
+) -> Result<bool, FinalErrorResponse> {
+    Ok(!super::roles::Role::get_user_roles(user_id, executor)
+        .await?
+        .is_empty())
+}
+
+/// Returns whether the user has TOTP enabled, and whether it is mandatory for them
+pub async fn get_status(
+    user_id: i32,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(bool, bool), FinalErrorResponse> {
+    let data = get_totp_data(user_id, executor).await?;
+    let is_required = is_required(user_id, executor).await?;
+    Ok((data.totp_enabled, is_required))
+}
+
+pub async fn create_challenge(
+    user_id: i32,
+    enrolment_required: bool,
+    executor: &mut sqlx::PgConnection,
+) -> Result<TwoFactorChallenge, FinalErrorResponse> {
+    let challenge_token = super::generate_session_token();
+
+    sqlx::query(
+        r#"
+            INSERT INTO tokens (token, user_id, token_type)
+            VALUES($1, $2, 'two_factor_challenge'::token_type)
+        "#,
+    )
+    .bind(&challenge_token)
+    .bind(user_id)
+    .execute(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(TwoFactorChallenge {
+        challenge_token,
+        enrolment_required,
+    })
+}
+
+pub async fn get_challenge_user_id(
+    challenge_token: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<i32, FinalErrorResponse> {
+    sqlx::query_scalar(
+        r#"
+            SELECT user_id
+            FROM tokens
+            WHERE
+                token = $1 AND
+                token_type = 'two_factor_challenge'::token_type AND
+                time >= NOW() - INTERVAL '10 minutes'
+        "#,
+    )
+    .bind(challenge_token)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+    .ok_or(EveryReturnedError::InvalidTwoFactorChallenge.into_final_error(""))
+}
+
+/// Issues the session once a challenge has been completed
+pub async fn complete_challenge(
+    challenge_token: &str,
+    user_id: i32,
+    ip: IpAddr,
+    user_agent: Option<String>,
+    executor: &mut sqlx::PgConnection,
+) -> Result<LogInData, FinalErrorResponse> {
+    sqlx::query(
+        "DELETE FROM tokens WHERE token = $1 AND token_type = 'two_factor_challenge'::token_type",
+    )
+    .bind(challenge_token)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Sessions::insert(executor, user_id, ip, user_agent).await
+}
+
+/// Generates a new secret, which only becomes active once a code generated
+/// from it is confirmed through [`confirm_enrolment`]
+pub async fn begin_enrolment(
+    user_id: i32,
+    executor: &mut sqlx::PgConnection,
+) -> Result<TotpEnrolment, FinalErrorResponse> {
+    let secret = generate_base32::<TOTP_SECRET_BYTES>();
+
+    let username: String = sqlx::query_scalar(
+        r#"
+            UPDATE users
+            SET totp_secret = $2
+            WHERE
+                id = $1 AND
+                totp_enabled = FALSE
+            RETURNING username
+        "#,
+    )
+    .bind(user_id)
+    .bind(&secret)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+    .ok_or(EveryReturnedError::NothingChanged.into_final_error("TOTP is already enabled"))?;
+
+    let label = percent_encoding::utf8_percent_encode(
+        &format!("{TOTP_ISSUER}:{username}"),
+        percent_encoding::NON_ALPHANUMERIC,
+    )
+    .to_string();
+
+    Ok(TotpEnrolment {
+        otpauth_uri: format!(
+            "otpauth://totp/{label}?secret={secret}&issuer={TOTP_ISSUER}&digits={TOTP_DIGITS}&period={TOTP_STEP_SECONDS}"
+        ),
+        secret,
+    })
+}
+
+/// Enables TOTP for the user and returns the recovery codes,
+/// which are only ever shown this once
+pub async fn confirm_enrolment(
+    user_id: i32,
+    code: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<Vec<String>, FinalErrorResponse> {
+    let data = get_totp_data(user_id, executor).await?;
+    let secret = match (data.totp_enabled, data.totp_secret) {
+        (false, Some(secret)) => secret,
+        _ => return Err(EveryReturnedError::InvalidInput.into_final_error("")),
+    };
+
+    let counter = find_totp_counter(&secret, code)
+        .ok_or(EveryReturnedError::InvalidTwoFactorCode.into_final_error(""))?;
+
+    sqlx::query("UPDATE users SET totp_enabled = TRUE, totp_last_counter = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(counter)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    regenerate_recovery_codes(user_id, executor).await
+}
+
+pub async fn regenerate_recovery_codes(
+    user_id: i32,
+    executor: &mut sqlx::PgConnection,
+) -> Result<Vec<String>, FinalErrorResponse> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_base32::<RECOVERY_CODE_BYTES>())
+        .collect();
+    let hashes: Vec<String> = codes.iter().map(|x| hash_recovery_code(x)).collect();
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query(
+        "INSERT INTO totp_recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::VARCHAR[])",
+    )
+    .bind(user_id)
+    .bind(hashes)
+    .execute(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(codes)
+}
+
+/// Checks a TOTP code, refusing codes for a time step that has already been used
+pub async fn verify_code(
+    user_id: i32,
+    code: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let data = get_totp_data(user_id, executor).await?;
+    let secret = match (data.totp_enabled, data.totp_secret) {
+        (true, Some(secret)) => secret,
+        _ => return Err(EveryReturnedError::InvalidTwoFactorCode.into_final_error("")),
+    };
+
+    let counter = match find_totp_counter(&secret, code) {
+        Some(v) if data.totp_last_counter.is_none_or(|last| v > last) => v,
+        _ => return Err(EveryReturnedError::InvalidTwoFactorCode.into_final_error("")),
+    };
+
+    sqlx::query("UPDATE users SET totp_last_counter = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(counter)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
+/// Recovery codes can only be used once
+pub async fn consume_recovery_code(
+    user_id: i32,
+    code: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let result =
+        sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1 AND code_hash = $2")
+            .bind(user_id)
+            .bind(hash_recovery_code(code))
+            .execute(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    match result.rows_affected() {
+        0 => Err(EveryReturnedError::InvalidTwoFactorCode.into_final_error("")),
+        _ => Ok(()),
+    }
+}
+
+/// Verifies either a TOTP code or a recovery code against a login challenge,
+/// failed attempts count towards the login cooldown
+pub async fn verify_challenge(
+    challenge_token: &str,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+    ip: IpAddr,
+    user_agent: Option<String>,
+    executor: &mut sqlx::PgConnection,
+) -> Result<LogInData, FinalErrorResponse> {
+    let user_id = get_challenge_user_id(challenge_token, executor).await?;
+
+    if LogInAttempts::is_on_cooldown(
+        LogInAttempts::get_from_sql(executor, ip, user_id).await?,
+        ip,
+        user_id,
+    ) {
+        return Err(EveryReturnedError::UserOnCooldown.into_final_error(""));
+    }
+
+    let result = match (code, recovery_code) {
+        (Some(code), _) => verify_code(user_id, code, executor).await,
+        (None, Some(recovery_code)) => {
+            consume_recovery_code(user_id, recovery_code, executor).await
+        }
+        (None, None) => Err(EveryReturnedError::InvalidTwoFactorCode.into_final_error("")),
+    };
+
+    if let Err(e) = result {
+        LogInAttempts::insert(executor, ip, user_id).await?;
+        return Err(e);
+    }
+
+    complete_challenge(challenge_token, user_id, ip, user_agent, executor).await
+}
+
+pub async fn disable(
+    user_id: i32,
+    code: &str,
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    if is_required(user_id, executor).await? {
+        return Err(EveryReturnedError::TwoFactorRequired.into_final_error(""));
+    }
+
+    verify_code(user_id, code, executor).await?;
+
+    sqlx::query(
+        r#"
+            UPDATE users
+            SET
+                totp_secret = NULL,
+                totp_enabled = FALSE,
+                totp_last_counter = NULL
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_totp_counter_at, hash_recovery_code, hotp};
+
+    /// The RFC 4226 and RFC 6238 test secret, "12345678901234567890"
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn generates_rfc_4226_codes() {
+        let secret = b"12345678901234567890";
+        let codes = (0..5).map(|x| hotp(secret, x)).collect::<Vec<_>>();
+        assert_eq!(codes, [755224, 287082, 359152, 969429, 338314]);
+    }
+
+    #[test]
+    fn finds_the_counter_of_rfc_6238_codes() {
+        assert_eq!(find_totp_counter_at(SECRET, "287082", 59), Some(1));
+        assert_eq!(
+            find_totp_counter_at(SECRET, "081804", 1111111109),
+            Some(1111111109 / 30),
+            "leading zeros are kept"
+        );
+        assert_eq!(
+            find_totp_counter_at(SECRET, " 005924\n", 1234567890),
+            Some(1234567890 / 30)
+        );
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift() {
+        // The code for counter 1, checked from counters 0 to 3
+        assert_eq!(find_totp_counter_at(SECRET, "287082", 0), Some(1));
+        assert_eq!(find_totp_counter_at(SECRET, "287082", 89), Some(1));
+        assert_eq!(find_totp_counter_at(SECRET, "287082", 90), None);
+        assert_eq!(find_totp_counter_at(SECRET, "287082", 3000), None);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(find_totp_counter_at(SECRET, "28708x", 59), None);
+        assert_eq!(find_totp_counter_at(SECRET, "", 59), None);
+        assert_eq!(find_totp_counter_at("not base32!", "287082", 59), None);
+    }
+
+    #[test]
+    fn normalizes_recovery_codes() {
+        assert_eq!(
+            hash_recovery_code("abcd-efgh-ijkl"),
+            hash_recovery_code("ABCDEFGHIJKL")
+        );
+        assert_ne!(
+            hash_recovery_code("ABCDEFGHIJKL"),
+            hash_recovery_code("ABCDEFGHIJKM")
+        );
+    }
+}
@@ -1,4 +1,8 @@
+use std::sync::LazyLock;
+
+use argon2::{PasswordHasher, PasswordVerifier};
 use base64::Engine;
+use sha1::Digest;
 
 use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
 
@@ -7,19 +11,104 @@ use super::ValidatedString;
 #[derive(Clone, serde::Serialize)]
 pub struct Password(String);
 
-impl ValidatedString for Password {
-    fn new_from_string(val: String) -> Result<Self, FinalErrorResponse> {
-        let val = match val.len() {
-            0..=8 => return Err(EveryReturnedError::PasswordTooShort.into_final_error("")),
-            129.. => return Err(EveryReturnedError::PasswordTooLong.into_final_error("")),
-            _ => val,
-        };
+pub enum PasswordMatch {
+    Mismatch,
+    Match,
+    /// The password is correct, but the stored hash is in the old format
+    /// or uses outdated parameters
+    NeedsRehash,
+}
+
+fn argon2_instance() -> argon2::Argon2<'static> {
+    argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        argon2::Params::new(
+            crate::ENV_VARS.argon2_memory_cost,
+            crate::ENV_VARS.argon2_time_cost,
+            crate::ENV_VARS.argon2_parallelism,
+            None,
+        )
+        .expect("Invalid argon2 parameters"),
+    )
+}
+
+/// Parses a list in the same format as the Have I Been Pwned downloads
+/// (`HASH:COUNT`, the count is ignored), sorted for binary search
+fn parse_breached_password_hashes(file: &str) -> Vec<[u8; 20]> {
+    let mut hashes = file
+        .lines()
+        .filter_map(|line| {
+            let hash = line.split(':').next()?.trim();
+            let hash = data_encoding::HEXUPPER_PERMISSIVE
+                .decode(hash.as_bytes())
+                .ok()?;
+            hash.try_into().ok()
+        })
+        .collect::<Vec<[u8; 20]>>();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+/// SHA-1 hashes of known breached passwords, read once from `PASSWORD_BREACH_LIST`.
+/// A list that can't be read only disables the check
+static BREACHED_PASSWORD_HASHES: LazyLock<Vec<[u8; 20]>> = LazyLock::new(|| {
+    let path = &crate::ENV_VARS.password_breach_list;
+    if path.is_empty() {
+        return vec![];
+    }
+
+    println!("- Loading breached password list");
+    match std::fs::read_to_string(path) {
+        Ok(file) => parse_breached_password_hashes(&file),
+        Err(e) => {
+            println!("Couldn't read breached password list {path}: {e}");
+            vec![]
+        }
+    }
+});
+
+pub fn load_breached_password_list() -> usize {
+    BREACHED_PASSWORD_HASHES.len()
+}
+
+struct PasswordPolicy {
+    min_length: usize,
+    max_length: usize,
+    require_uppercase: bool,
+    require_lowercase: bool,
+    require_special: bool,
+    require_number: bool,
+}
+
+impl PasswordPolicy {
+    fn from_env() -> Self {
+        Self {
+            min_length: crate::ENV_VARS.password_min_length as usize,
+            max_length: crate::ENV_VARS.password_max_length as usize,
+            require_uppercase: crate::ENV_VARS.password_require_uppercase,
+            require_lowercase: crate::ENV_VARS.password_require_lowercase,
+            require_special: crate::ENV_VARS.password_require_special,
+            require_number: crate::ENV_VARS.password_require_number,
+        }
+    }
+
+    /// The first rule the password breaks, if any
+    fn violation(&self, password: &str) -> Option<EveryReturnedError> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Some(EveryReturnedError::PasswordTooShort);
+        }
+        if length > self.max_length {
+            return Some(EveryReturnedError::PasswordTooLong);
+        }
 
         let mut has_uppercase = false;
         let mut has_lowercase = false;
         let mut has_special_character = false;
         let mut has_number = false;
-        for character in val.chars() {
+        for character in password.chars() {
             if !character.is_alphanumeric() {
                 has_special_character = true;
                 continue;
@@ -41,17 +130,33 @@ impl ValidatedString for Password {
             }
         }
 
-        if !has_uppercase {
-            return Err(EveryReturnedError::PasswordMustHaveUppercase.into_final_error(""));
+        if self.require_uppercase && !has_uppercase {
+            return Some(EveryReturnedError::PasswordMustHaveUppercase);
+        }
+        if self.require_lowercase && !has_lowercase {
+            return Some(EveryReturnedError::PasswordMustHaveLowercase);
         }
-        if !has_lowercase {
-            return Err(EveryReturnedError::PasswordMustHaveLowercase.into_final_error(""));
+        if self.require_special && !has_special_character {
+            return Some(EveryReturnedError::PasswordMustHaveSpecial);
         }
-        if !has_special_character {
-            return Err(EveryReturnedError::PasswordMustHaveSpecial.into_final_error(""));
+        if self.require_number && !has_number {
+            return Some(EveryReturnedError::PasswordMustHaveNumber);
+        }
+
+        None
+    }
+}
+
+impl ValidatedString for Password {
+    /// Enforces the password policy, only to be used when a password is being set
+    fn new_from_string(val: String) -> Result<Self, FinalErrorResponse> {
+        if let Some(violation) = PasswordPolicy::from_env().violation(&val) {
+            return Err(violation.into_final_error(""));
         }
-        if !has_number {
-            return Err(EveryReturnedError::PasswordMustHaveNumber.into_final_error(""));
+
+        let hash: [u8; 20] = sha1::Sha1::digest(val.as_bytes()).into();
+        if BREACHED_PASSWORD_HASHES.binary_search(&hash).is_ok() {
+            return Err(EveryReturnedError::PasswordBreached.into_final_error(""));
         }
 
         Ok(Self(val))
@@ -63,12 +168,79 @@ impl ValidatedString for Password {
 }
 
 impl Password {
-    pub fn hash(self, salt: &[u8]) -> String {
+    /// For passwords that are only checked against a stored hash, so that
+    /// changes to the policy don't lock anyone out of their account
+    pub fn new_for_verification(val: String) -> Result<Self, FinalErrorResponse> {
+        if val.chars().count() > crate::ENV_VARS.password_max_length as usize {
+            return Err(EveryReturnedError::PasswordTooLong.into_final_error(""));
+        }
+        Ok(Self(val))
+    }
+
+    /// Hashes into a PHC string, which embeds the algorithm, parameters and salt
+    pub fn hash(&self) -> String {
+        self.hash_with(&argon2_instance())
+    }
+
+    fn hash_with(&self, argon: &argon2::Argon2) -> String {
+        let salt = argon2::password_hash::SaltString::generate(
+            &mut argon2::password_hash::rand_core::OsRng,
+        );
+        argon
+            .hash_password(self.0.as_bytes(), &salt)
+            .expect("Password failed to hash, this should be infallible")
+            .to_string()
+    }
+
+    /// `legacy_salt` is only used for hashes from before the PHC format,
+    /// which stored the salt in its own column
+    pub fn verify(&self, stored_hash: &str, legacy_salt: Option<&str>) -> PasswordMatch {
+        self.verify_with(&argon2_instance(), stored_hash, legacy_salt)
+    }
+
+    /// Hashes made with other parameters than `argon`'s still verify, but need a rehash
+    fn verify_with(
+        &self,
+        argon: &argon2::Argon2,
+        stored_hash: &str,
+        legacy_salt: Option<&str>,
+    ) -> PasswordMatch {
+        let Ok(parsed_hash) = argon2::PasswordHash::new(stored_hash) else {
+            return match legacy_salt {
+                Some(salt) if self.legacy_hash(salt.as_bytes()) == stored_hash => {
+                    PasswordMatch::NeedsRehash
+                }
+                _ => PasswordMatch::Mismatch,
+            };
+        };
+
+        if argon
+            .verify_password(self.0.as_bytes(), &parsed_hash)
+            .is_err()
+        {
+            return PasswordMatch::Mismatch;
+        }
+
+        let is_outdated = parsed_hash.algorithm != argon2::Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(argon2::Version::V0x13.into())
+            || argon2::Params::try_from(&parsed_hash).map_or(true, |params| {
+                params.m_cost() != argon.params().m_cost()
+                    || params.t_cost() != argon.params().t_cost()
+                    || params.p_cost() != argon.params().p_cost()
+            });
+
+        match is_outdated {
+            true => PasswordMatch::NeedsRehash,
+            false => PasswordMatch::Match,
+        }
+    }
+
+    fn legacy_hash(&self, salt: &[u8]) -> String {
         let argon = argon2::Argon2::default();
 
         let mut hash_bytes = [0u8; 189];
         argon
-            .hash_password_into(self.get_inner().as_bytes(), salt, &mut hash_bytes)
+            .hash_password_into(self.0.as_bytes(), salt, &mut hash_bytes)
             .expect("Password failed to hash, this should be infallible");
 
         let token_engine = base64::engine::GeneralPurpose::new(
@@ -81,3 +253,160 @@ impl Password {
         out_string
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Password, PasswordMatch, PasswordPolicy, parse_breached_password_hashes};
+    use crate::api::errors::EveryReturnedError;
+    use sha1::Digest;
+
+    fn argon(memory_cost: u32, algorithm: argon2::Algorithm) -> argon2::Argon2<'static> {
+        argon2::Argon2::new(
+            algorithm,
+            argon2::Version::V0x13,
+            argon2::Params::new(memory_cost, 1, 1, None).unwrap(),
+        )
+    }
+
+    const POLICY: PasswordPolicy = PasswordPolicy {
+        min_length: 9,
+        max_length: 16,
+        require_uppercase: true,
+        require_lowercase: true,
+        require_special: true,
+        require_number: true,
+    };
+
+    #[test]
+    fn accepts_passwords_following_the_policy() {
+        assert!(POLICY.violation("Abcdefg1!").is_none());
+        assert!(POLICY.violation("Ärger über 9€").is_none());
+    }
+
+    #[test]
+    fn counts_length_in_characters() {
+        assert!(matches!(
+            POLICY.violation("Abcdef1!"),
+            Some(EveryReturnedError::PasswordTooShort)
+        ));
+        assert!(matches!(
+            POLICY.violation("Abcdefgh1!abcdefg"),
+            Some(EveryReturnedError::PasswordTooLong)
+        ));
+        assert!(
+            POLICY.violation("Éééééé1!x").is_none(),
+            "multi-byte characters count once"
+        );
+    }
+
+    #[test]
+    fn reports_missing_character_classes() {
+        assert!(matches!(
+            POLICY.violation("abcdefg1!"),
+            Some(EveryReturnedError::PasswordMustHaveUppercase)
+        ));
+        assert!(matches!(
+            POLICY.violation("ABCDEFG1!"),
+            Some(EveryReturnedError::PasswordMustHaveLowercase)
+        ));
+        assert!(matches!(
+            POLICY.violation("Abcdefgh1"),
+            Some(EveryReturnedError::PasswordMustHaveSpecial)
+        ));
+        assert!(matches!(
+            POLICY.violation("Abcdefgh!"),
+            Some(EveryReturnedError::PasswordMustHaveNumber)
+        ));
+
+        let relaxed = PasswordPolicy {
+            require_uppercase: false,
+            require_special: false,
+            require_number: false,
+            ..POLICY
+        };
+        assert!(relaxed.violation("abcdefghi").is_none());
+    }
+
+    #[test]
+    fn parses_breached_password_lists() {
+        // SHA-1 of "password" and "123456"
+        let hashes = parse_breached_password_hashes(concat!(
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\n",
+            "7c4a8d09ca3762af61e59520943dc26494f8941b\n",
+            "not a hash\n",
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:1\n",
+        ));
+        assert_eq!(hashes.len(), 2);
+        assert!(hashes.is_sorted());
+
+        let hash: [u8; 20] = sha1::Sha1::digest(b"password").into();
+        assert!(hashes.binary_search(&hash).is_ok());
+    }
+
+    #[test]
+    fn verifies_current_hashes() {
+        let current = argon(256, argon2::Algorithm::Argon2id);
+        let password = Password(String::from("correct horse"));
+        let hash = password.hash_with(&current);
+
+        assert!(hash.starts_with("$argon2id$v=19$m=256,t=1,p=1$"));
+        assert!(matches!(
+            password.verify_with(&current, &hash, None),
+            PasswordMatch::Match
+        ));
+        assert!(matches!(
+            Password(String::from("wrong horse")).verify_with(&current, &hash, None),
+            PasswordMatch::Mismatch
+        ));
+    }
+
+    #[test]
+    fn upgrades_outdated_hashes() {
+        let current = argon(256, argon2::Algorithm::Argon2id);
+        let password = Password(String::from("correct horse"));
+
+        let old_params = password.hash_with(&argon(128, argon2::Algorithm::Argon2id));
+        assert!(matches!(
+            password.verify_with(&current, &old_params, None),
+            PasswordMatch::NeedsRehash
+        ));
+
+        let old_algorithm = password.hash_with(&argon(256, argon2::Algorithm::Argon2i));
+        assert!(matches!(
+            password.verify_with(&current, &old_algorithm, None),
+            PasswordMatch::NeedsRehash
+        ));
+        assert!(matches!(
+            Password(String::from("wrong horse")).verify_with(&current, &old_params, None),
+            PasswordMatch::Mismatch
+        ));
+    }
+
+    #[test]
+    fn upgrades_legacy_hashes() {
+        let current = argon(256, argon2::Algorithm::Argon2id);
+        let password = Password(String::from("correct horse"));
+        let legacy = password.legacy_hash(b"legacy salt");
+
+        assert!(matches!(
+            password.verify_with(&current, &legacy, Some("legacy salt")),
+            PasswordMatch::NeedsRehash
+        ));
+        assert!(matches!(
+            password.verify_with(&current, &legacy, Some("other salt")),
+            PasswordMatch::Mismatch
+        ));
+        assert!(matches!(
+            password.verify_with(&current, &legacy, None),
+            PasswordMatch::Mismatch
+        ));
+        assert!(matches!(
+            Password(String::from("wrong horse")).verify_with(
+                &current,
+                &legacy,
+                Some("legacy salt")
+            ),
+            PasswordMatch::Mismatch
+        ));
+    }
+}
@@ -212,3 +212,29 @@ impl ChadsoftIDConversion for Vec<i64> {
             .collect()
     }
 }
+
+impl ChadsoftIDConversion for Option<i64> {
+    fn serialize_as_string<S>(x: &Self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match x {
+            Some(v) => i64::serialize_as_string(v, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    fn deserialize_from_string<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where
+        D: Deserializer<'de>,
+        Self: Sized,
+    {
+        let x: Option<&str> = serde::de::Deserialize::deserialize(deserializer)?;
+        x.map(|x| {
+            u64::from_str_radix(x, 16)
+                .map(|x| x as i64)
+                .map_err(|_| serde::de::Error::custom("Could not convert Chadsoft ID"))
+        })
+        .transpose()
+    }
+}
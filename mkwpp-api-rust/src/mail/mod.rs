@@ -1,12 +1,17 @@
-use mail_send::{
-    Credentials, SmtpClientBuilder, mail_builder::MessageBuilder, smtp::message::IntoMessage,
-};
+use mail_send::{Credentials, SmtpClientBuilder, smtp::message::IntoMessage};
 
 use crate::{
     ENV_VARS,
     api::errors::{EveryReturnedError, FinalErrorResponse},
 };
 
+pub mod notifications;
+pub mod outbox;
+pub mod templates;
+
+use outbox::OutboxEmail;
+use templates::EmailTemplate;
+
 const MKWPP_NAME: &str = "Mario Kart Wii Players' Page";
 const MKWPP_EMAIL: &str = "no-reply@mariokart64.com";
 
@@ -37,48 +42,97 @@ impl MailService {
         Ok(())
     }
 
+    /// Renders one of the templates in `EMAIL_TEMPLATES_DIR` and adds it to the outbox.
+    /// `{dns}` is available to every template
+    pub async fn send_template(
+        executor: &mut sqlx::PgConnection,
+        username: &str,
+        email: &str,
+        locale: &str,
+        template: &str,
+        values: &[(&str, &str)],
+    ) -> Result<(), FinalErrorResponse> {
+        let values = [&[("dns", ENV_VARS.server_dns.as_str())], values].concat();
+        let template = EmailTemplate::render(template, locale, &values)?;
+
+        OutboxEmail::enqueue(executor, username, email, template).await
+    }
+
     pub async fn account_verification(
+        executor: &mut sqlx::PgConnection,
         username: &str,
         email: &str,
+        locale: &str,
         token: &str,
     ) -> Result<(), FinalErrorResponse> {
-        Self::send_message(
-            MessageBuilder::new()
-                .from((MKWPP_NAME, MKWPP_EMAIL))
-                .to((username, email))
-                .subject("Account Verification")
-                .text_body(format!(
-                    include_str!(concat!(
-                        env!("CARGO_MANIFEST_DIR"),
-                        "/../email_text/verify_account.txt"
-                    )),
-                    username = username,
-                    token = token,
-                    dns = ENV_VARS.server_dns
-                )),
+        Self::send_template(
+            executor,
+            username,
+            email,
+            locale,
+            "verify_account",
+            &[
+                ("username", username),
+                ("token", token),
+                (
+                    "expiry_hours",
+                    &ENV_VARS.activation_token_expiry.to_string(),
+                ),
+            ],
         )
         .await
     }
 
     pub async fn password_reset(
+        executor: &mut sqlx::PgConnection,
         username: &str,
         email: &str,
+        locale: &str,
+        token: &str,
+    ) -> Result<(), FinalErrorResponse> {
+        Self::send_template(
+            executor,
+            username,
+            email,
+            locale,
+            "password_reset",
+            &[("username", username), ("token", token)],
+        )
+        .await
+    }
+
+    pub async fn email_change_confirmation(
+        executor: &mut sqlx::PgConnection,
+        username: &str,
+        new_email: &str,
+        locale: &str,
         token: &str,
     ) -> Result<(), FinalErrorResponse> {
-        Self::send_message(
-            MessageBuilder::new()
-                .from((MKWPP_NAME, MKWPP_EMAIL))
-                .to((username, email))
-                .subject("Account Verification")
-                .text_body(format!(
-                    include_str!(concat!(
-                        env!("CARGO_MANIFEST_DIR"),
-                        "/../email_text/password_reset.txt"
-                    )),
-                    username = username,
-                    token = token,
-                    dns = ENV_VARS.server_dns
-                )),
+        Self::send_template(
+            executor,
+            username,
+            new_email,
+            locale,
+            "change_email_confirm",
+            &[("username", username), ("token", token)],
+        )
+        .await
+    }
+
+    pub async fn email_change_notice(
+        executor: &mut sqlx::PgConnection,
+        username: &str,
+        old_email: &str,
+        locale: &str,
+        new_email: &str,
+    ) -> Result<(), FinalErrorResponse> {
+        Self::send_template(
+            executor,
+            username,
+            old_email,
+            locale,
+            "change_email_notice",
+            &[("username", username), ("new_email", new_email)],
         )
         .await
     }
@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    sql::tables::{
+        Category,
+        regions::RegionType,
+        scores::record_holders::{RecordChange, RecordHolder},
+        submissions::SubmissionStatus,
+    },
+};
+
+use super::MailService;
+
+/// Which notification emails a user opted into. Everything is off by default
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferences {
+    pub submission_reviews: bool,
+    pub lost_records: bool,
+    pub site_champions: bool,
+}
+
+impl NotificationPreferences {
+    pub async fn get(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+    ) -> Result<Self, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT submission_reviews, lost_records, site_champions
+                FROM notification_preferences
+                WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+        .map(Option::unwrap_or_default)
+    }
+
+    pub async fn set(
+        &self,
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        sqlx::query(
+            r#"
+                INSERT INTO notification_preferences
+                    (user_id, submission_reviews, lost_records, site_champions)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id) DO UPDATE SET
+                    submission_reviews = EXCLUDED.submission_reviews,
+                    lost_records = EXCLUDED.lost_records,
+                    site_champions = EXCLUDED.site_champions
+            "#,
+        )
+        .bind(user_id)
+        .bind(self.submission_reviews)
+        .bind(self.lost_records)
+        .bind(self.site_champions)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct Recipient {
+    username: String,
+    email: String,
+    locale: String,
+}
+
+pub fn category_name(category: &Category) -> &'static str {
+    match category {
+        Category::NonSc => "Non-SC",
+        Category::Sc => "SC",
+        Category::Unres => "Unrestricted",
+    }
+}
+
+pub fn chart_name(track_abbr: &str, is_lap: bool) -> String {
+    format!("{track_abbr} {}", if is_lap { "Lap" } else { "Course" })
+}
+
+/// Times are stored in milliseconds, shown as `m:ss.mmm`
+pub fn format_time(value: i32) -> String {
+    format!(
+        "{}:{:02}.{:03}",
+        value / 60000,
+        (value / 1000) % 60,
+        value % 1000
+    )
+}
+
+/// Queues a notification without letting it fail the caller. The emails are
+/// written inside a savepoint, so a broken template or outbox insert is
+/// rolled back and logged while the score or review still goes through
+pub async fn send_or_log(
+    executor: &mut sqlx::PgConnection,
+    name: &str,
+    send: impl AsyncFnOnce(&mut sqlx::PgConnection) -> Result<(), FinalErrorResponse>,
+) {
+    let mut savepoint = match sqlx::Connection::begin(&mut *executor).await {
+        Ok(savepoint) => savepoint,
+        Err(e) => {
+            println!("Could not queue {name} notification: {e}");
+            return;
+        }
+    };
+
+    match send(&mut savepoint).await {
+        Ok(()) => {
+            if let Err(e) = savepoint.commit().await {
+                println!("Could not queue {name} notification: {e}");
+            }
+        }
+        Err(e) => {
+            println!("Could not queue {name} notification: {}", e.messages());
+            if let Err(e) = savepoint.rollback().await {
+                println!("Could not roll back {name} notification: {e}");
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum ReviewedItem {
+    Submission,
+    EditSubmission,
+}
+
+#[derive(sqlx::FromRow)]
+struct ReviewOutcome {
+    submitter_id: i32,
+    username: String,
+    email: String,
+    locale: String,
+    status: SubmissionStatus,
+    reviewer_note: Option<String>,
+    value: i32,
+    category: Category,
+    is_lap: bool,
+    track_abbr: String,
+}
+
+/// Tells the submitter that a moderator accepted or rejected their submission.
+/// Nothing is sent to moderators reviewing their own submissions
+pub async fn submission_reviewed(
+    executor: &mut sqlx::PgConnection,
+    item: ReviewedItem,
+    id: i32,
+    reviewer_id: i32,
+) -> Result<(), FinalErrorResponse> {
+    let query = match item {
+        ReviewedItem::Submission => {
+            r#"
+                SELECT
+                    users.id AS submitter_id, users.username, users.email, users.locale,
+                    submissions.status, submissions.reviewer_note,
+                    submissions.value, submissions.category, submissions.is_lap,
+                    tracks.abbr AS track_abbr
+                FROM submissions
+                JOIN users ON users.id = submissions.submitter_id
+                JOIN notification_preferences ON
+                    notification_preferences.user_id = users.id AND
+                    notification_preferences.submission_reviews
+                JOIN tracks ON tracks.id = submissions.track_id
+                WHERE submissions.id = $1
+            "#
+        }
+        ReviewedItem::EditSubmission => {
+            r#"
+                SELECT
+                    users.id AS submitter_id, users.username, users.email, users.locale,
+                    edit_submissions.status, edit_submissions.reviewer_note,
+                    scores.value, scores.category, scores.is_lap,
+                    tracks.abbr AS track_abbr
+                FROM edit_submissions
+                JOIN users ON users.id = edit_submissions.submitter_id
+                JOIN notification_preferences ON
+                    notification_preferences.user_id = users.id AND
+                    notification_preferences.submission_reviews
+                JOIN scores ON scores.id = edit_submissions.score_id
+                JOIN tracks ON tracks.id = scores.track_id
+                WHERE edit_submissions.id = $1
+            "#
+        }
+    };
+
+    let Some(outcome) = sqlx::query_as::<_, ReviewOutcome>(query)
+        .bind(id)
+        .fetch_optional(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+    else {
+        return Ok(());
+    };
+
+    if outcome.submitter_id == reviewer_id {
+        return Ok(());
+    }
+
+    let status = match outcome.status {
+        SubmissionStatus::Pending => return Ok(()),
+        SubmissionStatus::Accepted => "accepted",
+        SubmissionStatus::Rejected => "rejected",
+        SubmissionStatus::OnHold => "put on hold",
+    };
+
+    MailService::send_template(
+        executor,
+        &outcome.username,
+        &outcome.email,
+        &outcome.locale,
+        "submission_reviewed",
+        &[
+            ("username", &outcome.username),
+            (
+                "kind",
+                match item {
+                    ReviewedItem::Submission => "submission",
+                    ReviewedItem::EditSubmission => "edit request",
+                },
+            ),
+            ("time", &format_time(outcome.value)),
+            ("chart", &chart_name(&outcome.track_abbr, outcome.is_lap)),
+            ("category", category_name(&outcome.category)),
+            ("status", status),
+            (
+                "reviewer_note",
+                outcome.reviewer_note.as_deref().unwrap_or("-"),
+            ),
+        ],
+    )
+    .await
+}
+
+/// Tells every subscribed player whose record got beaten on a chart.
+/// One email is sent per player, listing every region and category the record was lost in
+pub async fn records_lost(
+    executor: &mut sqlx::PgConnection,
+    track_id: i32,
+    is_lap: bool,
+    changes: &[RecordChange<'_>],
+) -> Result<(), FinalErrorResponse> {
+    let mut lost: BTreeMap<i32, Vec<(&RecordHolder, &RecordChange)>> = BTreeMap::new();
+    for change in changes {
+        for old in change.lost_by() {
+            lost.entry(old.player_id).or_default().push((old, change));
+        }
+    }
+
+    if lost.is_empty() {
+        return Ok(());
+    }
+
+    let track_abbr: String = sqlx::query_scalar("SELECT abbr FROM tracks WHERE id = $1")
+        .bind(track_id)
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    let chart = chart_name(&track_abbr, is_lap);
+
+    for (player_id, records) in lost {
+        let recipients = sqlx::query_as::<_, Recipient>(
+            r#"
+                SELECT users.username, users.email, users.locale
+                FROM users
+                JOIN notification_preferences ON
+                    notification_preferences.user_id = users.id AND
+                    notification_preferences.lost_records
+                WHERE users.player_id = $1
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        let regions = records
+            .iter()
+            .map(|(old, _)| match old.region_type {
+                RegionType::World => String::from("World Record"),
+                _ => old.region_code.clone(),
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>()
+            .join(", ");
+        let categories = records
+            .iter()
+            .map(|(old, _)| old.category)
+            .collect::<BTreeSet<_>>()
+            .iter()
+            .map(category_name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let Some(new) = records
+            .iter()
+            .map(|(_, change)| change.region())
+            .min_by_key(|x| x.value)
+        else {
+            continue;
+        };
+
+        for recipient in recipients {
+            MailService::send_template(
+                executor,
+                &recipient.username,
+                &recipient.email,
+                &recipient.locale,
+                "record_lost",
+                &[
+                    ("username", &recipient.username),
+                    ("chart", &chart),
+                    ("regions", &regions),
+                    ("categories", &categories),
+                    ("new_holder", &new.player_name),
+                    ("time", &format_time(new.value)),
+                ],
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct NewChamp {
+    category: Category,
+    player_name: String,
+    recent: bool,
+}
+
+/// Announces site champions that haven't been announced yet to every subscribed user.
+/// Only reigns that started in the last week are announced, so imports stay quiet
+pub async fn site_champions(executor: &mut sqlx::PgConnection) -> Result<(), FinalErrorResponse> {
+    let champs = sqlx::query_as::<_, NewChamp>(
+        r#"
+            UPDATE site_champs
+            SET notified = TRUE
+            FROM players
+            WHERE
+                players.id = site_champs.player_id AND
+                NOT site_champs.notified
+            RETURNING
+                site_champs.category,
+                players.name AS player_name,
+                site_champs.date_instated >= CURRENT_DATE - 7 AS recent
+        "#,
+    )
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    let champs = champs.into_iter().filter(|x| x.recent).collect::<Vec<_>>();
+    if champs.is_empty() {
+        return Ok(());
+    }
+
+    let recipients = sqlx::query_as::<_, Recipient>(
+        r#"
+            SELECT users.username, users.email, users.locale
+            FROM users
+            JOIN notification_preferences ON
+                notification_preferences.user_id = users.id AND
+                notification_preferences.site_champions
+            WHERE users.is_active
+        "#,
+    )
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    for champ in champs {
+        for recipient in &recipients {
+            MailService::send_template(
+                executor,
+                &recipient.username,
+                &recipient.email,
+                &recipient.locale,
+                "site_champion",
+                &[
+                    ("username", &recipient.username),
+                    ("player", &champ.player_name),
+                    ("category", category_name(&champ.category)),
+                ],
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use mail_send::mail_builder::MessageBuilder;
+
+use crate::{
+    ENV_VARS,
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    custom_serde::DateAsTimestampNumber,
+};
+
+use super::{MKWPP_EMAIL, MKWPP_NAME, MailService, templates::EmailTemplate};
+
+/// Emails picked up by the worker on each tick
+const BATCH_SIZE: i64 = 25;
+
+#[derive(sqlx::Type, serde::Serialize, Debug, PartialEq, Clone)]
+#[sqlx(type_name = "email_status", rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum EmailStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEmail {
+    pub id: i32,
+    pub recipient_name: String,
+    pub recipient_email: String,
+    pub subject: String,
+    #[serde(skip)]
+    pub text_body: String,
+    #[serde(skip)]
+    pub html_body: Option<String>,
+    pub status: EmailStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub next_attempt: chrono::DateTime<chrono::Utc>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub sent_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Queue overview shown to admins
+#[serde_with::skip_serializing_none]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxStatus {
+    pub pending: i64,
+    pub sent: i64,
+    pub failed: i64,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub oldest_pending: Option<chrono::DateTime<chrono::Utc>>,
+    /// Most recent emails that are still pending or have failed
+    pub unsent: Vec<OutboxEmail>,
+}
+
+impl OutboxEmail {
+    /// Adds an email to the outbox. It only gets sent if the caller's transaction commits
+    pub async fn enqueue(
+        executor: &mut sqlx::PgConnection,
+        username: &str,
+        email: &str,
+        template: EmailTemplate,
+    ) -> Result<(), FinalErrorResponse> {
+        sqlx::query(
+            r#"
+                INSERT INTO email_outbox (recipient_name, recipient_email, subject, text_body, html_body)
+                VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(username)
+        .bind(email)
+        .bind(template.subject)
+        .bind(template.text_body)
+        .bind(template.html_body)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
+    pub async fn get_status(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<OutboxStatus, FinalErrorResponse> {
+        let (pending, sent, failed, oldest_pending): (
+            i64,
+            i64,
+            i64,
+            Option<chrono::DateTime<chrono::Utc>>,
+        ) = sqlx::query_as(
+            r#"
+                SELECT
+                    COUNT(*) FILTER (WHERE status = 'pending'),
+                    COUNT(*) FILTER (WHERE status = 'sent'),
+                    COUNT(*) FILTER (WHERE status = 'failed'),
+                    MIN(created) FILTER (WHERE status = 'pending')
+                FROM email_outbox
+            "#,
+        )
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        let unsent = sqlx::query_as(
+            "SELECT * FROM email_outbox WHERE status != 'sent' ORDER BY created DESC LIMIT 100",
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(OutboxStatus {
+            pending,
+            sent,
+            failed,
+            oldest_pending,
+            unsent,
+        })
+    }
+
+    /// Puts a failed email back in the queue with a fresh set of attempts
+    pub async fn retry(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query(
+            r#"
+                UPDATE email_outbox
+                SET status = 'pending', attempts = 0, next_attempt = NOW()
+                WHERE id = $1 AND status = 'failed'
+            "#,
+        )
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    /// Sends the email through SMTP, or writes it to `EMAIL_OUTPUT_DIR` as `<id>.eml` if set
+    async fn deliver(&self) -> Result<(), FinalErrorResponse> {
+        let mut message = MessageBuilder::new()
+            .from((MKWPP_NAME, MKWPP_EMAIL))
+            .to((self.recipient_name.as_str(), self.recipient_email.as_str()))
+            .subject(self.subject.as_str())
+            .text_body(self.text_body.as_str());
+        if let Some(html_body) = &self.html_body {
+            message = message.html_body(html_body.as_str());
+        }
+
+        if ENV_VARS.email_output_dir.is_empty() {
+            return MailService::send_message(message).await;
+        }
+
+        let contents = message
+            .write_to_vec()
+            .map_err(|e| EveryReturnedError::SendingEmail.into_final_error(e))?;
+        let dir = std::path::Path::new(&ENV_VARS.email_output_dir);
+        std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(dir.join(format!("{}.eml", self.id)), contents))
+            .map_err(|e| EveryReturnedError::SendingEmail.into_final_error(e))
+    }
+
+    /// Delivers one batch of due emails. Failed attempts are retried with
+    /// exponential backoff until `EMAIL_MAX_ATTEMPTS` is reached.
+    /// Emails are claimed before being sent, so no lock is held while talking
+    /// to the mail server and a crashed worker only means the email is retried later
+    async fn process_batch(executor: &mut sqlx::PgConnection) -> Result<(), FinalErrorResponse> {
+        let emails: Vec<Self> = sqlx::query_as(
+            r#"
+                WITH due AS (
+                    SELECT id FROM email_outbox
+                    WHERE status = 'pending' AND next_attempt <= NOW()
+                    ORDER BY next_attempt
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE email_outbox
+                SET
+                    attempts = email_outbox.attempts + 1,
+                    next_attempt = NOW() + make_interval(secs => $2 * power(2, LEAST(email_outbox.attempts, 16)))
+                FROM due
+                WHERE email_outbox.id = due.id
+                RETURNING email_outbox.*
+            "#,
+        )
+        .bind(BATCH_SIZE)
+        .bind(ENV_VARS.email_retry_delay as f64)
+        .fetch_all(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        for email in emails {
+            let query = match email.deliver().await {
+                Ok(()) => sqlx::query(
+                    "UPDATE email_outbox SET status = 'sent', sent_at = NOW() WHERE id = $1",
+                )
+                .bind(email.id),
+                Err(e) => sqlx::query(
+                    r#"
+                        UPDATE email_outbox
+                        SET
+                            last_error = $2,
+                            status = CASE
+                                WHEN attempts >= $3 THEN 'failed'::email_status
+                                ELSE 'pending'::email_status
+                            END
+                        WHERE id = $1
+                    "#,
+                )
+                .bind(email.id)
+                .bind(e.messages())
+                .bind(ENV_VARS.email_max_attempts as i32),
+            };
+
+            // The claim already pushed `next_attempt` back, so a lost result only means a resend
+            if let Err(e) = query.execute(&mut *executor).await {
+                println!("Could not record outbox email {}: {e}", email.id);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn worker() {
+    let mut interval = tokio::time::interval(core::time::Duration::new(
+        crate::ENV_VARS.email_outbox_interval.max(1),
+        0,
+    ));
+    loop {
+        interval.tick().await;
+        let app_state = crate::app_state::access_app_state().await;
+        let mut executor = {
+            let app_state_guard = app_state.read().await;
+            match app_state_guard.acquire_pg_connection().await {
+                Ok(v) => v,
+                Err(_) => continue,
+            }
+        };
+
+        let _ = OutboxEmail::process_batch(&mut executor).await;
+    }
+}
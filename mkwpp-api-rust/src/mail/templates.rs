@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+/// Templates are read from `{EMAIL_TEMPLATES_DIR}/{locale}/{name}.txt`, with an
+/// optional `{name}.html` alternative next to it. The plain-text file starts with
+/// a `Subject: ...` line followed by an empty line. Both bodies and the subject
+/// can use `{placeholder}` values, with `{{` and `}}` for literal braces.
+/// Templates are loaded on every send, so wording changes don't need a rebuild
+pub struct EmailTemplate {
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+}
+
+/// Only letters, digits, `-` and `_`, since locales end up in file paths
+pub fn is_valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= 16
+        && locale
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
+fn template_path(locale: &str, name: &str, extension: &str) -> PathBuf {
+    PathBuf::from(&crate::ENV_VARS.email_templates_dir)
+        .join(locale)
+        .join(format!("{name}.{extension}"))
+}
+
+fn fill_placeholders(
+    template: &str,
+    values: &[(&str, &str)],
+    escape: fn(&str) -> String,
+) -> Result<String, FinalErrorResponse> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let end = match (rest.starts_with('{'), rest.find('}')) {
+            (true, Some(v)) => v,
+            _ => {
+                return Err(EveryReturnedError::LoadingEmailTemplate
+                    .into_final_error("Unmatched brace in email template"));
+            }
+        };
+
+        let key = &rest[1..end];
+        match values.iter().find(|(x, _)| *x == key) {
+            Some((_, value)) => out.push_str(&escape(value)),
+            None => {
+                return Err(EveryReturnedError::LoadingEmailTemplate
+                    .into_final_error(format!("Unknown placeholder {{{key}}}")));
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(character),
+        }
+    }
+    out
+}
+
+impl EmailTemplate {
+    /// Falls back to `EMAIL_DEFAULT_LOCALE` when the template doesn't exist in `locale`
+    pub fn render(
+        name: &str,
+        locale: &str,
+        values: &[(&str, &str)],
+    ) -> Result<Self, FinalErrorResponse> {
+        let default_locale = crate::ENV_VARS.email_default_locale.as_str();
+        let locale = match is_valid_locale(locale) && template_path(locale, name, "txt").exists() {
+            true => locale,
+            false => default_locale,
+        };
+
+        let text = std::fs::read_to_string(template_path(locale, name, "txt"))
+            .map_err(|e| EveryReturnedError::LoadingEmailTemplate.into_final_error(e))?;
+        let (subject, text_body) = text
+            .strip_prefix("Subject:")
+            .and_then(|x| x.split_once('\n'))
+            .ok_or(
+                EveryReturnedError::LoadingEmailTemplate
+                    .into_final_error(format!("Missing subject line in template {name}")),
+            )?;
+
+        let html_body = match std::fs::read_to_string(template_path(locale, name, "html")) {
+            Ok(v) => Some(fill_placeholders(&v, values, escape_html)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(EveryReturnedError::LoadingEmailTemplate.into_final_error(e)),
+        };
+
+        Ok(Self {
+            subject: fill_placeholders(subject.trim(), values, str::to_string)?,
+            text_body: fill_placeholders(
+                text_body.trim_start_matches(['\r', '\n']),
+                values,
+                str::to_string,
+            )?,
+            html_body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_html, fill_placeholders};
+
+    fn fill(template: &str, values: &[(&str, &str)]) -> Option<String> {
+        fill_placeholders(template, values, str::to_string).ok()
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        assert_eq!(
+            fill("Hi {username}, welcome!", &[("username", "Kyle")]).as_deref(),
+            Some("Hi Kyle, welcome!")
+        );
+    }
+
+    #[test]
+    fn fills_repeated_placeholders() {
+        assert_eq!(
+            fill("{a}-{b}-{a}", &[("a", "1"), ("b", "2")]).as_deref(),
+            Some("1-2-1")
+        );
+    }
+
+    #[test]
+    fn ignores_unused_values() {
+        assert_eq!(
+            fill("No placeholders", &[("username", "Kyle")]).as_deref(),
+            Some("No placeholders")
+        );
+    }
+
+    #[test]
+    fn rejects_missing_values() {
+        assert_eq!(fill("Hi {username}", &[]), None);
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert_eq!(fill("Hi {usernmae}", &[("username", "Kyle")]), None);
+        assert_eq!(fill("Hi {}", &[("username", "Kyle")]), None);
+    }
+
+    #[test]
+    fn rejects_unmatched_braces() {
+        assert_eq!(fill("Hi {username", &[("username", "Kyle")]), None);
+        assert_eq!(fill("Hi username}", &[("username", "Kyle")]), None);
+    }
+
+    #[test]
+    fn keeps_escaped_braces() {
+        assert_eq!(
+            fill("{{literal}} {username}", &[("username", "Kyle")]).as_deref(),
+            Some("{literal} Kyle")
+        );
+    }
+
+    #[test]
+    fn does_not_fill_placeholders_inside_values() {
+        assert_eq!(
+            fill("{a} {b}", &[("a", "{b}"), ("b", "x")]).as_deref(),
+            Some("{b} x")
+        );
+    }
+
+    #[test]
+    fn escapes_values_in_html() {
+        assert_eq!(
+            fill_placeholders(
+                r#"<a href="{link}">{username}</a>"#,
+                &[
+                    ("link", r#"https://example.com/?a=1&b="2""#),
+                    ("username", "<script>alert('x')</script>"),
+                ],
+                escape_html,
+            )
+            .ok()
+            .as_deref(),
+            Some(
+                r#"<a href="https://example.com/?a=1&amp;b=&quot;2&quot;">&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</a>"#
+            )
+        );
+    }
+
+    #[test]
+    fn does_not_escape_values_in_text() {
+        assert_eq!(
+            fill("{username}", &[("username", "<b>Kyle</b> & co")]).as_deref(),
+            Some("<b>Kyle</b> & co")
+        );
+    }
+}
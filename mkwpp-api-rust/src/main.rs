@@ -3,7 +3,9 @@ mod app_state;
 mod auth;
 mod custom_serde;
 mod mail;
+mod markdown;
 mod sql;
+mod webhooks;
 
 use std::sync::LazyLock;
 
@@ -32,6 +34,10 @@ async fn main() -> std::io::Result<()> {
         ENV_VARS.client_request_timeout
     );
     println!("| SERVER CONNECTION KEEP ALIVE: {}", ENV_VARS.keep_alive);
+    println!(
+        "| BREACHED PASSWORD HASHES: {}",
+        auth::validated_strings::password::load_breached_password_list()
+    );
 
     import_data().await;
     {
@@ -56,6 +62,12 @@ async fn main() -> std::io::Result<()> {
     println!("- Starting Cache Update Loop");
     tokio::task::spawn(app_state::cache::update_loop());
 
+    println!("- Starting Email Outbox Worker");
+    tokio::task::spawn(mail::outbox::worker());
+
+    println!("- Starting Webhook Delivery Worker");
+    tokio::task::spawn(webhooks::worker());
+
     println!("- Enabling environment logger");
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
@@ -64,6 +76,7 @@ async fn main() -> std::io::Result<()> {
         let cors = Cors::permissive();
 
         App::new()
+            .wrap(middleware::from_fn(auth::sessions::track_client))
             .wrap(cors)
             .wrap(middleware::NormalizePath::new(
                 middleware::TrailingSlash::Trim,
@@ -0,0 +1,213 @@
+//! Renders user written Markdown with pulldown-cmark, then runs the output
+//! through ammonia. Raw HTML is shown as text, and links only keep safe
+//! schemes, so the output can be embedded as is
+
+use std::{borrow::Cow, collections::HashSet, sync::LazyLock};
+
+use pulldown_cmark::{Event, Options, Parser};
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .link_rel(Some("nofollow"))
+        .add_tag_attributes("code", ["class"])
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            // Images are only loaded from the web or the site itself
+            ("img", "src")
+                if value
+                    .trim_start()
+                    .to_ascii_lowercase()
+                    .starts_with("mailto:") =>
+            {
+                None
+            }
+            // Code blocks keep their language for syntax highlighting, and nothing else
+            ("code", "class") => value
+                .strip_prefix("language-")
+                .filter(|x| {
+                    !x.is_empty()
+                        && x.len() <= 32
+                        && x.chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+'))
+                })
+                .map(|_| Cow::Borrowed(value)),
+            _ => Some(Cow::Borrowed(value)),
+        });
+    builder
+});
+
+pub fn render(markdown: &str) -> String {
+    let parser =
+        Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH).map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            event => event,
+        });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    SANITIZER.clean(&html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    #[test]
+    fn drops_script_links() {
+        let unlinked = "<p><a rel=\"nofollow\">click</a></p>\n";
+        assert_eq!(render("[click](javascript:alert(1))"), unlinked);
+        assert_eq!(render("[click](JavaScript:alert(1))"), unlinked);
+        assert_eq!(render("[click]( javascript:alert(1))"), unlinked);
+        assert_eq!(
+            render("[click](data:text/html;base64,PHNjcmlwdD4=)"),
+            unlinked
+        );
+        assert_eq!(
+            render("![alt](data:image/png;base64,AAAA)"),
+            "<p><img alt=\"alt\"></p>\n"
+        );
+        assert_eq!(
+            render("<javascript:alert(1)>"),
+            "<p><a rel=\"nofollow\">javascript:alert(1)</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_web_mail_and_relative_links() {
+        assert_eq!(
+            render("[a](https://example.com) [b](mailto:a@b.c) [c](/mkw/records?x=a:b)"),
+            concat!(
+                r#"<p><a href="https://example.com" rel="nofollow">a</a> "#,
+                r#"<a href="mailto:a@b.c" rel="nofollow">b</a> "#,
+                r#"<a href="/mkw/records?x=a:b" rel="nofollow">c</a></p>"#,
+                "\n"
+            )
+        );
+        assert_eq!(
+            render("<https://example.com>"),
+            "<p><a href=\"https://example.com\" rel=\"nofollow\">https://example.com</a></p>\n"
+        );
+        assert_eq!(
+            render("![mail](mailto:a@b.c)"),
+            "<p><img alt=\"mail\"></p>\n",
+            "images can't point at mail addresses"
+        );
+    }
+
+    #[test]
+    fn escapes_raw_html() {
+        assert_eq!(
+            render("<script>alert(1)</script>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+        assert_eq!(
+            render("<img src=x onerror=alert(1)>"),
+            "&lt;img src=x onerror=alert(1)&gt;"
+        );
+        assert_eq!(
+            render("```\n<script>\n```"),
+            "<pre><code>&lt;script&gt;\n</code></pre>\n"
+        );
+        assert_eq!(render("`<b>`"), "<p><code>&lt;b&gt;</code></p>\n");
+        assert_eq!(render("a <b>b</b> c"), "<p>a &lt;b&gt;b&lt;/b&gt; c</p>\n");
+    }
+
+    #[test]
+    fn quotes_attributes() {
+        assert_eq!(
+            render(r#"[a](https://example.com/"onmouseover="alert(1))"#),
+            concat!(
+                r#"<p><a href="https://example.com/%22onmouseover=%22alert(1)" "#,
+                r#"rel="nofollow">a</a></p>"#,
+                "\n"
+            )
+        );
+        assert_eq!(
+            render(r#"![a" onerror="alert(1)](/x.png)"#),
+            "<p><img src=\"/x.png\" alt=\"a&quot; onerror=&quot;alert(1)\"></p>\n"
+        );
+        assert_eq!(
+            render("```js\" onclick=\"alert(1)\ncode\n```"),
+            "<pre><code>code\n</code></pre>\n"
+        );
+        assert_eq!(
+            render("```rust\ncode\n```"),
+            "<pre><code class=\"language-rust\">code\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn nests_emphasis() {
+        assert_eq!(
+            render("*a **b** c*"),
+            "<p><em>a <strong>b</strong> c</em></p>\n"
+        );
+        assert_eq!(render("***a***"), "<p><em><strong>a</strong></em></p>\n");
+        assert_eq!(render("~~a *b*~~"), "<p><del>a <em>b</em></del></p>\n");
+        assert_eq!(render("snake_case_name"), "<p>snake_case_name</p>\n");
+        assert_eq!(render("*a"), "<p>*a</p>\n");
+        assert_eq!(render("a * b * c"), "<p>a * b * c</p>\n");
+        assert_eq!(render(r"\*a\*"), "<p>*a*</p>\n");
+    }
+
+    #[test]
+    fn renders_deep_nesting() {
+        let quotes = render(&format!("{}a", ">".repeat(100)));
+        assert_eq!(quotes.matches("<blockquote>").count(), 100);
+        assert_eq!(quotes.matches("</blockquote>").count(), 100);
+
+        let emphasis = render(&format!("{0}a{0}", "*a ".repeat(100).trim_end()));
+        assert_eq!(
+            emphasis.matches("<em>").count(),
+            emphasis.matches("</em>").count()
+        );
+    }
+
+    #[test]
+    fn renders_lists() {
+        assert_eq!(render("- a\n- b"), "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n");
+        assert_eq!(
+            render("3. a\n4. b"),
+            "<ol start=\"3\">\n<li>a</li>\n<li>b</li>\n</ol>\n"
+        );
+        assert_eq!(
+            render("- a\n\n- b"),
+            "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>\n",
+            "blank lines between items keep the list going"
+        );
+        assert_eq!(
+            render("- a\n  - b\n- c"),
+            "<ul>\n<li>a\n<ul>\n<li>b</li>\n</ul>\n</li>\n<li>c</li>\n</ul>\n"
+        );
+        assert_eq!(
+            render("- a\nb"),
+            "<ul>\n<li>a\nb</li>\n</ul>\n",
+            "lazy continuation lines stay in the item"
+        );
+        assert_eq!(
+            render("- a\n1. b"),
+            "<ul>\n<li>a</li>\n</ul>\n<ol>\n<li>b</li>\n</ol>\n"
+        );
+        assert_eq!(render("- - -"), "<hr>\n");
+        assert_eq!(render("-a"), "<p>-a</p>\n");
+        assert_eq!(render("1234567890. a"), "<p>1234567890. a</p>\n");
+    }
+
+    #[test]
+    fn leaves_unclosed_delimiters_as_text() {
+        for input in [
+            "*a ".repeat(50_000),
+            "**a ".repeat(50_000),
+            "_a ".repeat(50_000),
+            "<".repeat(100_000),
+            "<a".repeat(50_000),
+            (1..400).rev().map(|x| "`".repeat(x) + "a").collect(),
+        ] {
+            let html = render(&input);
+            assert!(html.starts_with("<p>"));
+            assert!(!html.contains("<em>") && !html.contains("<strong>"));
+            assert!(!html.contains("<code>") && !html.contains("<a"));
+        }
+    }
+}
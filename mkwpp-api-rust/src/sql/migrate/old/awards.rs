@@ -24,7 +24,7 @@ impl super::OldFixtureJson for Awards {
             )
             .unwrap(),
             date: chrono::NaiveDate::parse_from_str(&self.date, "%F").unwrap(),
-            description: self.description,
+            description: Some(self.description),
             player_id: self.player,
         }
         .insert_or_replace_query(transaction)
@@ -45,6 +45,7 @@ impl super::OldFixtureJson for BlogPosts {
             ),
             author_id: Some(user_id),
             username: None,
+            tags: Vec::new(),
         }
         .upsert(transaction)
         .await;
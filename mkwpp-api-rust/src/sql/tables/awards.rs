@@ -1,4 +1,8 @@
-use crate::custom_serde::DateAsTimestampNumber;
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    custom_serde::DateAsTimestampNumber,
+};
+use sqlx::postgres::PgRow;
 
 #[derive(sqlx::Type, serde::Serialize, serde::Deserialize, Debug)]
 #[sqlx(type_name = "player_award_type", rename_all = "snake_case")]
@@ -31,10 +35,23 @@ pub struct Awards {
         deserialize_with = "DateAsTimestampNumber::deserialize_from_timestamp"
     )]
     pub date: chrono::NaiveDate,
-    pub description: String,
+    pub description: Option<String>,
     pub player_award_type: AwardType,
 }
 
 impl super::BasicTableQueries for Awards {
     const TABLE_NAME: &'static str = "player_awards";
 }
+
+impl Awards {
+    pub async fn filter_by_player(
+        player_id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<PgRow>, FinalErrorResponse> {
+        return sqlx::query("SELECT * FROM player_awards WHERE player_id = $1 ORDER BY date DESC;")
+            .bind(player_id)
+            .fetch_all(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+}
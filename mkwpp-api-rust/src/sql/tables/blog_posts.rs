@@ -15,9 +15,86 @@ pub struct BlogPosts {
         deserialize_with = "DateAsTimestampNumber::deserialize_from_timestamp"
     )]
     pub published_at: chrono::DateTime<chrono::Utc>,
+    /// When read back, this is the author's player id rather than their user id
     pub author_id: Option<i32>,
-    #[sqlx(skip)]
     pub username: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 32;
+
+/// Filters for the public post list, every one of them optional
+#[derive(Default)]
+pub struct BlogListFilter {
+    pub limit: i32,
+    pub offset: i32,
+    pub tag: Option<String>,
+    /// Postgres `websearch_to_tsquery` syntax, empty to match everything
+    pub query: String,
+    /// Only posts older than this one are returned
+    pub cursor: Option<i32>,
+}
+
+/// A post as its authors see it, drafts and scheduled posts included
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EditableBlogPost {
+    pub id: i32,
+    pub title: String,
+    /// Empty for posts imported as HTML
+    pub markdown: Option<String>,
+    pub content: String,
+    pub is_published: bool,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub published_at: chrono::DateTime<chrono::Utc>,
+    pub author_id: i32,
+    pub username: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlogPostInput {
+    pub title: String,
+    pub markdown: String,
+    pub is_published: bool,
+    /// Defaults to now, a date in the future schedules the post
+    #[serde(
+        default,
+        deserialize_with = "DateAsTimestampNumber::deserialize_from_timestamp"
+    )]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A snapshot of a post, taken every time it's saved
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BlogPostRevision {
+    pub id: i32,
+    pub title: String,
+    pub markdown: String,
+    pub is_published: bool,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub published_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
+    pub editor_id: Option<i32>,
+    pub editor_username: Option<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BlogTag {
+    pub tag: String,
+    pub post_count: i64,
 }
 
 impl BasicTableQueries for BlogPosts {
@@ -25,26 +102,289 @@ impl BasicTableQueries for BlogPosts {
 }
 
 impl BlogPosts {
-    pub async fn get_limit(
-        limit: i32,
+    pub async fn get_list(
         executor: &mut sqlx::PgConnection,
+        filter: &BlogListFilter,
     ) -> Result<Vec<PgRow>, FinalErrorResponse> {
-        return sqlx::query(
-            "SELECT * FROM blog_posts WHERE is_published = true ORDER BY published_at DESC LIMIT $1",
-        )
-        .bind(limit)
+        return sqlx::query(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../db/queries/blog_list.sql"
+        )))
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .bind(&filter.tag)
+        .bind(filter.query.trim())
+        .bind(filter.cursor)
         .fetch_all(executor)
-        .await.map_err(| e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
 
     pub async fn get_by_id(
         id: i32,
         executor: &mut sqlx::PgConnection,
     ) -> Result<PgRow, FinalErrorResponse> {
-        return sqlx::query("SELECT * FROM blog_posts WHERE is_published = true AND id = $1")
+        return sqlx::query(
+            r#"
+                SELECT
+                    blog_posts.id, blog_posts.title,
+                    blog_posts.content, blog_posts.is_published,
+                    blog_posts.published_at, blog_posts.tags,
+                    users.player_id AS author_id, users.username
+                FROM blog_posts
+                LEFT JOIN users ON users.id = blog_posts.author_id
+                WHERE
+                    blog_posts.is_published AND
+                    blog_posts.published_at <= NOW() AND
+                    blog_posts.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+
+    /// Every tag in use on published posts, with how many posts have it
+    pub async fn get_tags(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<BlogTag>, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT tag, COUNT(*) AS post_count
+                FROM blog_posts, UNNEST(tags) AS tag
+                WHERE is_published AND published_at <= NOW()
+                GROUP BY tag
+                ORDER BY post_count DESC, tag
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn get_all_editable(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<EditableBlogPost>, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT
+                    blog_posts.id, blog_posts.title, blog_posts.markdown,
+                    blog_posts.content, blog_posts.is_published,
+                    blog_posts.published_at, blog_posts.author_id,
+                    users.username, blog_posts.tags, blog_posts.updated_at
+                FROM blog_posts
+                LEFT JOIN users ON users.id = blog_posts.author_id
+                ORDER BY blog_posts.published_at DESC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn get_editable(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<EditableBlogPost, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT
+                    blog_posts.id, blog_posts.title, blog_posts.markdown,
+                    blog_posts.content, blog_posts.is_published,
+                    blog_posts.published_at, blog_posts.author_id,
+                    users.username, blog_posts.tags, blog_posts.updated_at
+                FROM blog_posts
+                LEFT JOIN users ON users.id = blog_posts.author_id
+                WHERE blog_posts.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidInput.into_final_error("Unknown blog post"))
+    }
+
+    fn validate(input: &BlogPostInput) -> Result<(), FinalErrorResponse> {
+        let title = input.title.trim();
+        if title.is_empty() || title.chars().count() > 255 {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error("The title must be between 1 and 255 characters"));
+        }
+        Ok(())
+    }
+
+    /// Tags are stored lowercase and without duplicates
+    fn normalize_tags(tags: &[String]) -> Result<Vec<String>, FinalErrorResponse> {
+        let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = tag.trim().to_lowercase();
+            if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+                return Err(EveryReturnedError::InvalidInput.into_final_error(format!(
+                    "Tags must be between 1 and {MAX_TAG_LENGTH} characters"
+                )));
+            }
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        if normalized.len() > MAX_TAGS {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error(format!("A post can have at most {MAX_TAGS} tags")));
+        }
+        Ok(normalized)
+    }
+
+    async fn add_revision(
+        executor: &mut sqlx::PgConnection,
+        post_id: i32,
+        input: &BlogPostInput,
+        tags: &[String],
+        published_at: chrono::DateTime<chrono::Utc>,
+        editor_id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        sqlx::query(
+            r#"
+                INSERT INTO blog_post_revisions
+                    (post_id, title, markdown, is_published, published_at, tags, editor_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(post_id)
+        .bind(input.title.trim())
+        .bind(&input.markdown)
+        .bind(input.is_published)
+        .bind(published_at)
+        .bind(tags)
+        .bind(editor_id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
+    /// Returns the id of the new post
+    pub async fn create(
+        executor: &mut sqlx::PgConnection,
+        input: &BlogPostInput,
+        author_id: i32,
+    ) -> Result<i32, FinalErrorResponse> {
+        Self::validate(input)?;
+        let tags = Self::normalize_tags(&input.tags)?;
+        let published_at = input.published_at.unwrap_or_else(chrono::Utc::now);
+
+        let id = sqlx::query_scalar(
+            r#"
+                INSERT INTO blog_posts
+                    (title, markdown, content, is_published, published_at, tags, author_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id
+            "#,
+        )
+        .bind(input.title.trim())
+        .bind(&input.markdown)
+        .bind(crate::markdown::render(&input.markdown))
+        .bind(input.is_published)
+        .bind(published_at)
+        .bind(&tags)
+        .bind(author_id)
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Self::add_revision(executor, id, input, &tags, published_at, author_id).await?;
+
+        Ok(id)
+    }
+
+    /// Moving an announced post back to drafts or into the future
+    /// lets it be announced again once it goes live.
+    /// Imported posts only have HTML, which is kept until they're given some Markdown
+    pub async fn update(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+        input: &BlogPostInput,
+        editor_id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        Self::validate(input)?;
+        let tags = Self::normalize_tags(&input.tags)?;
+
+        let published_at: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+            r#"
+                UPDATE blog_posts
+                SET
+                    title = $2,
+                    markdown = CASE WHEN markdown IS NULL AND $8 THEN NULL ELSE $3 END,
+                    content = CASE WHEN markdown IS NULL AND $8 THEN content ELSE $4 END,
+                    is_published = $5,
+                    published_at = COALESCE($6, published_at),
+                    tags = $7,
+                    announced = announced AND $5 AND COALESCE($6, published_at) <= NOW(),
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING published_at
+            "#,
+        )
+        .bind(id)
+        .bind(input.title.trim())
+        .bind(&input.markdown)
+        .bind(crate::markdown::render(&input.markdown))
+        .bind(input.is_published)
+        .bind(input.published_at)
+        .bind(&tags)
+        .bind(input.markdown.trim().is_empty())
+        .fetch_optional(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        let Some(published_at) = published_at else {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        };
+
+        Self::add_revision(executor, id, input, &tags, published_at, editor_id).await
+    }
+
+    pub async fn delete(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query("DELETE FROM blog_posts WHERE id = $1")
             .bind(id)
-            .fetch_one(executor)
+            .execute(executor)
             .await
-            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    /// Newest first
+    pub async fn get_revisions(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<Vec<BlogPostRevision>, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT
+                    blog_post_revisions.id, blog_post_revisions.title,
+                    blog_post_revisions.markdown, blog_post_revisions.is_published,
+                    blog_post_revisions.published_at, blog_post_revisions.tags,
+                    blog_post_revisions.editor_id,
+                    users.username AS editor_username, blog_post_revisions.created
+                FROM blog_post_revisions
+                LEFT JOIN users ON users.id = blog_post_revisions.editor_id
+                WHERE blog_post_revisions.post_id = $1
+                ORDER BY blog_post_revisions.created DESC, blog_post_revisions.id DESC
+            "#,
+        )
+        .bind(id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
     }
 }
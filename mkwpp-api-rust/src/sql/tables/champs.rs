@@ -19,6 +19,24 @@ pub struct Champs {
     pub date_instated: chrono::NaiveDate,
 }
 
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ChampReigns {
+    pub id: i32,
+    pub category: super::Category,
+    #[serde(
+        serialize_with = "DateAsTimestampNumber::serialize_as_timestamp",
+        deserialize_with = "DateAsTimestampNumber::deserialize_from_timestamp"
+    )]
+    pub date_instated: chrono::NaiveDate,
+    #[serde(
+        serialize_with = "DateAsTimestampNumber::serialize_as_timestamp",
+        deserialize_with = "DateAsTimestampNumber::deserialize_from_timestamp"
+    )]
+    pub date_ended: Option<chrono::NaiveDate>,
+}
+
 impl super::BasicTableQueries for Champs {
     const TABLE_NAME: &'static str = "site_champs";
 }
@@ -36,4 +54,32 @@ impl Champs {
         .await
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
+
+    pub async fn get_player_reigns(
+        player_id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<PgRow>, FinalErrorResponse> {
+        return sqlx::query(
+            r#"
+            SELECT
+                id,
+                category,
+                date_instated,
+                (
+                    SELECT MIN(next_champ.date_instated)
+                    FROM site_champs AS next_champ
+                    WHERE
+                        next_champ.category = site_champs.category AND
+                        next_champ.date_instated > site_champs.date_instated
+                ) AS date_ended
+            FROM site_champs
+            WHERE player_id = $1
+            ORDER BY date_instated ASC;
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
 }
@@ -0,0 +1,382 @@
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    custom_serde::DateAsTimestampNumber,
+    sql::tables::BasicTableQueries,
+};
+
+const MAX_COMMENT_LENGTH: usize = 5000;
+
+/// Columns shared by every comment listing. Deleted and removed comments
+/// lose their text unless `$2` is true, which only moderators get
+const COMMENT_COLUMNS: &str = r#"
+    comments.id, comments.blog_post_id, comments.score_id,
+    comments.parent_id, comments.author_id,
+    users.username, users.player_id, players.name AS player_name,
+    CASE WHEN $2 OR (comments.deleted_at IS NULL AND comments.removed_at IS NULL)
+        THEN comments.markdown END AS markdown,
+    CASE WHEN $2 OR (comments.deleted_at IS NULL AND comments.removed_at IS NULL)
+        THEN comments.content END AS content,
+    comments.created, comments.edited_at,
+    comments.deleted_at IS NOT NULL AS is_deleted,
+    comments.removed_at IS NOT NULL AS is_removed,
+    comments.removal_reason,
+    CASE WHEN $2 THEN comments.removed_by END AS removed_by
+"#;
+
+const COMMENT_JOINS: &str = r#"
+    LEFT JOIN users ON users.id = comments.author_id
+    LEFT JOIN players ON players.id = users.player_id
+"#;
+
+/// What a comment is attached to. Exactly one of the two ids is set
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentTarget {
+    pub blog_post_id: Option<i32>,
+    pub score_id: Option<i32>,
+}
+
+/// Replies point to their parent through `parent_id`, threads are built by the client
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Comments {
+    pub id: i32,
+    pub blog_post_id: Option<i32>,
+    pub score_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub author_id: Option<i32>,
+    pub username: Option<String>,
+    pub player_id: Option<i32>,
+    pub player_name: Option<String>,
+    pub markdown: Option<String>,
+    /// Rendered from `markdown`
+    pub content: Option<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub is_deleted: bool,
+    pub is_removed: bool,
+    pub removal_reason: Option<String>,
+    pub removed_by: Option<i32>,
+}
+
+impl BasicTableQueries for Comments {
+    const TABLE_NAME: &'static str = "comments";
+}
+
+#[derive(sqlx::FromRow)]
+struct CommentState {
+    author_id: Option<i32>,
+    blog_post_id: Option<i32>,
+    score_id: Option<i32>,
+    is_visible: bool,
+}
+
+impl Comments {
+    /// Oldest first, so replies always come after their parent.
+    /// Blog posts that are drafts or scheduled for later have no comments to show
+    pub async fn get_for_target(
+        executor: &mut sqlx::PgConnection,
+        target: CommentTarget,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        Self::validate_target(target)?;
+
+        sqlx::query_as(&format!(
+            r#"
+                SELECT {COMMENT_COLUMNS}
+                FROM comments
+                {COMMENT_JOINS}
+                WHERE
+                    (
+                        comments.blog_post_id = $1 AND
+                        EXISTS(
+                            SELECT 1 FROM blog_posts
+                            WHERE id = $1 AND is_published AND published_at <= NOW()
+                        )
+                    ) OR
+                    comments.score_id = $3
+                ORDER BY comments.created, comments.id
+            "#
+        ))
+        .bind(target.blog_post_id)
+        .bind(false)
+        .bind(target.score_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Newest first, text of hidden comments included
+    pub async fn get_recent_for_moderation(
+        executor: &mut sqlx::PgConnection,
+        only_removed: bool,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(&format!(
+            r#"
+                SELECT {COMMENT_COLUMNS}
+                FROM comments
+                {COMMENT_JOINS}
+                WHERE NOT $1 OR comments.removed_at IS NOT NULL
+                ORDER BY comments.created DESC
+                LIMIT 100
+            "#
+        ))
+        .bind(only_removed)
+        .bind(true)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Every comment the user wrote, for the personal data export.
+    /// Which moderator removed a comment is not the user's data, so it is left out
+    pub async fn get_by_user_id(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as::<_, Self>(&format!(
+            r#"
+                SELECT {COMMENT_COLUMNS}
+                FROM comments
+                {COMMENT_JOINS}
+                WHERE comments.author_id = $1
+                ORDER BY comments.created
+            "#
+        ))
+        .bind(user_id)
+        .bind(true)
+        .fetch_all(executor)
+        .await
+        .map(|comments| {
+            comments
+                .into_iter()
+                .map(|comment| Self {
+                    removed_by: None,
+                    ..comment
+                })
+                .collect()
+        })
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    fn validate_target(target: CommentTarget) -> Result<(), FinalErrorResponse> {
+        match (target.blog_post_id, target.score_id) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(EveryReturnedError::InvalidInput
+                .into_final_error("Exactly one of blogPostId and scoreId must be set")),
+        }
+    }
+
+    fn validate_markdown(markdown: &str) -> Result<(), FinalErrorResponse> {
+        let length = markdown.trim().chars().count();
+        if length == 0 || length > MAX_COMMENT_LENGTH {
+            return Err(EveryReturnedError::InvalidInput.into_final_error(format!(
+                "Comments must be between 1 and {MAX_COMMENT_LENGTH} characters"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn get_state(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<CommentState, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT
+                    author_id, blog_post_id, score_id,
+                    deleted_at IS NULL AND removed_at IS NULL AS is_visible
+                FROM comments
+                WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidInput.into_final_error("Unknown comment"))
+    }
+
+    /// Comments can go on live blog posts and on any score.
+    /// Replies have to be on the same target as their parent, which must still be visible
+    pub async fn create(
+        executor: &mut sqlx::PgConnection,
+        target: CommentTarget,
+        parent_id: Option<i32>,
+        author_id: i32,
+        markdown: &str,
+    ) -> Result<i32, FinalErrorResponse> {
+        Self::validate_target(target)?;
+        Self::validate_markdown(markdown)?;
+
+        let target_exists: bool = sqlx::query_scalar(
+            r#"
+                SELECT
+                    EXISTS(
+                        SELECT 1 FROM blog_posts
+                        WHERE id = $1 AND is_published AND published_at <= NOW()
+                    ) OR EXISTS(
+                        SELECT 1 FROM scores WHERE id = $2
+                    )
+            "#,
+        )
+        .bind(target.blog_post_id)
+        .bind(target.score_id)
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if !target_exists {
+            return Err(
+                EveryReturnedError::InvalidInput.into_final_error("Unknown blog post or score")
+            );
+        }
+
+        if let Some(parent_id) = parent_id {
+            let parent = Self::get_state(executor, parent_id).await?;
+            if parent.blog_post_id != target.blog_post_id || parent.score_id != target.score_id {
+                return Err(EveryReturnedError::InvalidInput.into_final_error(
+                    "Replies must be on the same post or score as their parent",
+                ));
+            }
+            if !parent.is_visible {
+                return Err(EveryReturnedError::InvalidInput
+                    .into_final_error("Can't reply to a deleted comment"));
+            }
+        }
+
+        sqlx::query_scalar(
+            r#"
+                INSERT INTO comments (blog_post_id, score_id, parent_id, author_id, markdown, content)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+            "#,
+        )
+        .bind(target.blog_post_id)
+        .bind(target.score_id)
+        .bind(parent_id)
+        .bind(author_id)
+        .bind(markdown.trim())
+        .bind(crate::markdown::render(markdown.trim()))
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Only the author can edit, and only while the comment is visible
+    pub async fn edit(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+        user_id: i32,
+        markdown: &str,
+    ) -> Result<(), FinalErrorResponse> {
+        Self::validate_markdown(markdown)?;
+
+        let comment = Self::get_state(executor, id).await?;
+        if comment.author_id != Some(user_id) {
+            return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
+        }
+        if !comment.is_visible {
+            return Err(
+                EveryReturnedError::InvalidInput.into_final_error("Can't edit a deleted comment")
+            );
+        }
+
+        sqlx::query(
+            "UPDATE comments SET markdown = $2, content = $3, edited_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(markdown.trim())
+        .bind(crate::markdown::render(markdown.trim()))
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
+    /// Hides the comment, its replies stay visible
+    pub async fn delete(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+        user_id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let comment = Self::get_state(executor, id).await?;
+        if comment.author_id != Some(user_id) {
+            return Err(EveryReturnedError::InsufficientPermissions.into_final_error(""));
+        }
+
+        let result = sqlx::query(
+            "UPDATE comments SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+        moderator_id: i32,
+        reason: Option<&str>,
+    ) -> Result<(), FinalErrorResponse> {
+        let reason = reason.map(str::trim).filter(|x| !x.is_empty());
+        if reason.is_some_and(|x| x.chars().count() > 255) {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error("The removal reason can be at most 255 characters"));
+        }
+
+        let result = sqlx::query(
+            r#"
+                UPDATE comments
+                SET removed_at = NOW(), removed_by = $2, removal_reason = $3
+                WHERE id = $1 AND removed_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(moderator_id)
+        .bind(reason)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    /// Undoes a moderator removal. Comments deleted by their author stay deleted
+    pub async fn restore(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query(
+            r#"
+                UPDATE comments
+                SET removed_at = NULL, removed_by = NULL, removal_reason = NULL
+                WHERE id = $1 AND removed_at IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+}
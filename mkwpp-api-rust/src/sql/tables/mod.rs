@@ -3,6 +3,7 @@ use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
 pub mod awards;
 pub mod blog_posts;
 pub mod champs;
+pub mod comments;
 pub mod cups;
 pub mod players;
 pub mod regions;
@@ -0,0 +1,223 @@
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    custom_serde::{ChadsoftIDConversion, DateAsTimestampNumber},
+    sql::tables::submissions::SubmissionStatus,
+};
+
+/// A request from a user to be linked to a player profile.
+/// The optional Chadsoft ID is used as proof of ownership
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerClaims {
+    pub id: i32,
+    pub user_id: i32,
+    pub player_id: i32,
+    #[serde(serialize_with = "ChadsoftIDConversion::serialize_as_string")]
+    pub chadsoft_id: Option<i64>,
+    /// Whether the given Chadsoft ID is one of the player's known IDs
+    pub chadsoft_id_matches: bool,
+    pub user_note: Option<String>,
+    pub status: SubmissionStatus,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub submitted_at: chrono::DateTime<chrono::Utc>,
+    pub reviewer_id: Option<i32>,
+    pub reviewer_note: Option<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl super::super::BasicTableQueries for PlayerClaims {
+    const TABLE_NAME: &'static str = "player_claims";
+}
+
+const SELECT_CLAIMS: &str = r#"
+    SELECT
+        player_claims.*,
+        COALESCE(player_claims.chadsoft_id = ANY(players.chadsoft_ids), FALSE) AS chadsoft_id_matches
+    FROM player_claims
+    LEFT JOIN players ON players.id = player_claims.player_id
+"#;
+
+impl PlayerClaims {
+    pub async fn create(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        player_id: i32,
+        chadsoft_id: Option<i64>,
+        user_note: Option<String>,
+    ) -> Result<(), FinalErrorResponse> {
+        let (user_has_player, player_is_claimed, has_open_claim): (bool, bool, bool) =
+            sqlx::query_as(
+                r#"
+                    SELECT
+                        EXISTS(SELECT 1 FROM users WHERE id = $1 AND player_id IS NOT NULL),
+                        EXISTS(SELECT 1 FROM users WHERE player_id = $2),
+                        EXISTS(
+                            SELECT 1 FROM player_claims
+                            WHERE user_id = $1 AND status IN ('pending', 'on_hold')
+                        )
+                "#,
+            )
+            .bind(user_id)
+            .bind(player_id)
+            .fetch_one(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if user_has_player {
+            return Err(EveryReturnedError::UserAlreadyHasPlayer.into_final_error(""));
+        }
+        if player_is_claimed {
+            return Err(EveryReturnedError::PlayerAlreadyClaimed.into_final_error(""));
+        }
+        if has_open_claim {
+            return Err(EveryReturnedError::ClaimAlreadyOpen.into_final_error(""));
+        }
+
+        sqlx::query(
+            "INSERT INTO player_claims (user_id, player_id, chadsoft_id, user_note) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(player_id)
+        .bind(chadsoft_id)
+        .bind(user_note)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
+    pub async fn get_by_user_id(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(const_format::formatc!(
+            "{SELECT_CLAIMS} WHERE player_claims.user_id = $1 ORDER BY player_claims.submitted_at DESC"
+        ))
+        .bind(user_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Every claim that still needs a decision, oldest first
+    pub async fn get_open(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(const_format::formatc!(
+            "{SELECT_CLAIMS} WHERE player_claims.status IN ('pending', 'on_hold') ORDER BY player_claims.submitted_at ASC"
+        ))
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn cancel(
+        executor: &mut sqlx::PgConnection,
+        user_id: i32,
+        claim_id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query(
+            "DELETE FROM player_claims WHERE id = $1 AND user_id = $2 AND status IN ('pending', 'on_hold')",
+        )
+        .bind(claim_id)
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    /// On acceptance the user gets linked to the player, and every other
+    /// open claim on the same player is rejected
+    pub async fn review(
+        executor: &mut sqlx::PgConnection,
+        claim_id: i32,
+        reviewer_id: i32,
+        status: SubmissionStatus,
+        reviewer_note: Option<String>,
+    ) -> Result<(), FinalErrorResponse> {
+        if status == SubmissionStatus::Pending {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error("A claim cannot be reviewed as pending"));
+        }
+
+        let (user_id, player_id): (i32, i32) = sqlx::query_as(
+            r#"
+                UPDATE player_claims
+                SET
+                    status = $2,
+                    reviewer_id = $3,
+                    reviewer_note = $4,
+                    reviewed_at = NOW()
+                WHERE
+                    id = $1 AND
+                    status IN ('pending', 'on_hold')
+                RETURNING user_id, player_id
+            "#,
+        )
+        .bind(claim_id)
+        .bind(&status)
+        .bind(reviewer_id)
+        .bind(reviewer_note)
+        .fetch_optional(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidInput.into_final_error("Claim is not open for review"))?;
+
+        if status != SubmissionStatus::Accepted {
+            return Ok(());
+        }
+
+        let player_is_claimed: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE player_id = $1)")
+                .bind(player_id)
+                .fetch_one(&mut *executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if player_is_claimed {
+            return Err(EveryReturnedError::PlayerAlreadyClaimed.into_final_error(""));
+        }
+
+        let result =
+            sqlx::query("UPDATE users SET player_id = $1 WHERE id = $2 AND player_id IS NULL")
+                .bind(player_id)
+                .bind(user_id)
+                .execute(&mut *executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::UserAlreadyHasPlayer.into_final_error(""));
+        }
+
+        sqlx::query(
+            r#"
+                UPDATE player_claims
+                SET
+                    status = 'rejected',
+                    reviewer_id = $2,
+                    reviewer_note = 'This player has been linked to another account',
+                    reviewed_at = NOW()
+                WHERE
+                    player_id = $1 AND
+                    status IN ('pending', 'on_hold')
+            "#,
+        )
+        .bind(player_id)
+        .bind(reviewer_id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+}
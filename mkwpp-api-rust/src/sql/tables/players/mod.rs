@@ -2,7 +2,9 @@ use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
 use crate::custom_serde::{ChadsoftIDConversion, DateAsTimestampNumber};
 use crate::sql::tables::BasicTableQueries;
 
+pub mod claims;
 pub mod players_basic;
+pub mod profile;
 
 #[serde_with::skip_serializing_none]
 #[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
@@ -36,6 +38,20 @@ impl BasicTableQueries for Players {
     const TABLE_NAME: &'static str = "players";
 }
 
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerMergeSummary {
+    pub merged_player_id: i32,
+    pub into_player_id: i32,
+    pub scores_moved: u64,
+    pub submissions_moved: u64,
+    pub awards_moved: u64,
+    pub site_champs_moved: u64,
+    pub claims_moved: u64,
+    pub user_moved: bool,
+    pub charts_recalculated: usize,
+}
+
 impl Players {
     pub async fn insert_or_edit(
         executor: &mut sqlx::PgConnection,
@@ -71,6 +87,121 @@ impl Players {
         .execute(executor).await.map_err(| e | EveryReturnedError::GettingFromDatabase.into_final_error(e))
     }
 
+    /// Moves everything belonging to `from_id` onto `into_id`, then deletes `from_id`
+    pub async fn merge(
+        executor: &mut sqlx::PgConnection,
+        from_id: i32,
+        into_id: i32,
+    ) -> Result<PlayerMergeSummary, FinalErrorResponse> {
+        if from_id == into_id {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error("Cannot merge a player into itself"));
+        }
+
+        let found_players: Vec<i32> = sqlx::query_scalar(const_format::formatc!(
+            "SELECT id FROM {} WHERE id = ANY($1);",
+            Players::TABLE_NAME
+        ))
+        .bind([from_id, into_id])
+        .fetch_all(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if found_players.len() != 2 {
+            return Err(EveryReturnedError::InvalidInput.into_final_error("Player does not exist"));
+        }
+
+        let linked_players: Vec<i32> =
+            sqlx::query_scalar("SELECT player_id FROM users WHERE player_id = ANY($1);")
+                .bind([from_id, into_id])
+                .fetch_all(&mut *executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if linked_players.contains(&from_id) && linked_players.contains(&into_id) {
+            return Err(EveryReturnedError::InvalidInput
+                .into_final_error("Both players have an associated user"));
+        }
+
+        let charts: Vec<(i32, bool)> =
+            sqlx::query_as("SELECT DISTINCT track_id, is_lap FROM scores WHERE player_id = $1;")
+                .bind(from_id)
+                .fetch_all(&mut *executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        let mut moved_rows = Vec::with_capacity(6);
+        for table_name in [
+            crate::sql::tables::scores::Scores::TABLE_NAME,
+            crate::sql::tables::submissions::Submissions::TABLE_NAME,
+            crate::sql::tables::awards::Awards::TABLE_NAME,
+            crate::sql::tables::champs::Champs::TABLE_NAME,
+            claims::PlayerClaims::TABLE_NAME,
+            crate::auth::Users::TABLE_NAME,
+        ] {
+            moved_rows.push(
+                sqlx::query(&format!(
+                    "UPDATE {table_name} SET player_id = $2 WHERE player_id = $1;"
+                ))
+                .bind(from_id)
+                .bind(into_id)
+                .execute(&mut *executor)
+                .await
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+                .rows_affected(),
+            );
+        }
+
+        sqlx::query(const_format::formatc!(
+            r#"
+            UPDATE {players_table} AS into_player
+            SET
+                submitters = ARRAY(
+                    SELECT DISTINCT UNNEST(into_player.submitters || from_player.submitters)
+                ),
+                chadsoft_ids = ARRAY(
+                    SELECT DISTINCT UNNEST(into_player.chadsoft_ids || from_player.chadsoft_ids)
+                ),
+                joined_date = LEAST(into_player.joined_date, from_player.joined_date),
+                last_activity = GREATEST(into_player.last_activity, from_player.last_activity)
+            FROM {players_table} AS from_player
+            WHERE
+                into_player.id = $2 AND
+                from_player.id = $1;
+            "#,
+            players_table = Players::TABLE_NAME
+        ))
+        .bind(from_id)
+        .bind(into_id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Players::delete_by_id(from_id, executor).await?;
+
+        for (track_id, is_lap) in &charts {
+            crate::sql::tables::scores::Scores::update_was_wr(
+                *track_id,
+                crate::sql::tables::Category::Unres,
+                *is_lap,
+                executor,
+            )
+            .await?;
+        }
+
+        Ok(PlayerMergeSummary {
+            merged_player_id: from_id,
+            into_player_id: into_id,
+            scores_moved: moved_rows[0],
+            submissions_moved: moved_rows[1],
+            awards_moved: moved_rows[2],
+            site_champs_moved: moved_rows[3],
+            claims_moved: moved_rows[4],
+            user_moved: moved_rows[5] != 0,
+            charts_recalculated: charts.len(),
+        })
+    }
+
     pub async fn update_player_bio(
         executor: &mut sqlx::PgConnection,
         player_id: i32,
@@ -175,6 +306,18 @@ impl Players {
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
 
+    pub async fn get_name(
+        executor: &mut sqlx::PgConnection,
+        player_id: i32,
+    ) -> Result<String, FinalErrorResponse> {
+        return sqlx::query_scalar("SELECT name FROM players WHERE id = $1;")
+            .bind(player_id)
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+            .ok_or(EveryReturnedError::InvalidInput.into_final_error("Unknown player"));
+    }
+
     pub async fn get_player_id_from_user_id(
         executor: &mut sqlx::PgConnection,
         user_id: i32,
@@ -28,6 +28,28 @@ impl BasicTableQueries for PlayersBasic {
     }
 }
 
+impl PlayersBasic {
+    /// Case and accent insensitive search on name and alias, prefix matches
+    /// rank first and the rest is ordered by trigram similarity
+    pub async fn search(
+        executor: &mut sqlx::PgConnection,
+        query: &str,
+        region_ids: Vec<i32>,
+        limit: i32,
+    ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
+        return sqlx::query(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../db/queries/player_search.sql"
+        )))
+        .bind(query)
+        .bind(region_ids)
+        .bind(limit)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+}
+
 impl FilterPlayers for PlayersBasic {
     const GET_SELECT_PLAYERS_QUERY_STR: &'static str = const_format::formatc!(
         "SELECT id, name, alias, region_id FROM {} WHERE id = ANY($1);",
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::{
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::{decode_row_to_table, decode_rows_to_table},
+    },
+    custom_serde::DateAsTimestampNumber,
+    sql::tables::{
+        Category,
+        awards::{AwardType, Awards},
+        champs::{ChampReigns, Champs},
+        players::{FilterPlayers, Players},
+        regions::Regions,
+        scores::{Scores, ScoresByDate, timesheet::Timesheet},
+    },
+};
+
+const RECENT_SCORES_LIMIT: i32 = 20;
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerProfileCategoryStats {
+    pub category: Category,
+    pub af: f64,
+    pub arr: f64,
+    pub prwr: f64,
+    pub tally: i16,
+    pub total_time: i32,
+    pub records_held: i64,
+    pub records_ever_held: i64,
+    pub standard_levels: HashMap<String, u32>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerProfileAward {
+    pub id: i32,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub date: chrono::NaiveDate,
+    pub description: Option<String>,
+    pub player_award_type: AwardType,
+}
+
+impl From<Awards> for PlayerProfileAward {
+    fn from(value: Awards) -> Self {
+        Self {
+            id: value.id,
+            date: value.date,
+            description: value.description,
+            player_award_type: value.player_award_type,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerProfile {
+    pub player: Players,
+    pub region_ancestors: Vec<i32>,
+    pub categories: Vec<PlayerProfileCategoryStats>,
+    pub awards: Vec<PlayerProfileAward>,
+    pub site_champ_reigns: Vec<ChampReigns>,
+    pub recent_scores: Vec<ScoresByDate>,
+}
+
+impl PlayerProfile {
+    pub async fn get(
+        executor: &mut sqlx::PgConnection,
+        player_id: i32,
+    ) -> Result<Self, FinalErrorResponse> {
+        let player = match Players::get_select_players(executor, vec![player_id])
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(row) => decode_row_to_table::<Players>(row)?,
+            None => {
+                return Err(
+                    EveryReturnedError::InvalidInput.into_final_error("Player does not exist")
+                );
+            }
+        };
+
+        let region_ancestors = Regions::get_ancestors(executor, player.region_id).await?;
+
+        let today = chrono::Local::now().date_naive();
+        let mut categories = vec![];
+        for category in [Category::NonSc, Category::Sc, Category::Unres] {
+            let timesheet =
+                Timesheet::timesheet(executor, player_id, category, None, today, 1).await?;
+            let (records_held, records_ever_held) =
+                Scores::count_player_records(player_id, category, executor).await?;
+
+            let mut standard_levels = HashMap::new();
+            for time in timesheet.times.iter() {
+                *standard_levels
+                    .entry(time.std_lvl_code.clone())
+                    .or_insert(0) += 1;
+            }
+
+            categories.push(PlayerProfileCategoryStats {
+                category,
+                af: timesheet.af,
+                arr: timesheet.arr,
+                prwr: timesheet.prwr,
+                tally: timesheet.tally,
+                total_time: timesheet.total_time,
+                records_held,
+                records_ever_held,
+                standard_levels,
+            });
+        }
+
+        let awards =
+            decode_rows_to_table::<Awards>(Awards::filter_by_player(player_id, executor).await?)?
+                .into_iter()
+                .map(PlayerProfileAward::from)
+                .collect();
+        let site_champ_reigns = decode_rows_to_table::<ChampReigns>(
+            Champs::get_player_reigns(player_id, executor).await?,
+        )?;
+        let recent_scores = decode_rows_to_table::<ScoresByDate>(
+            ScoresByDate::order_player_by_date(executor, player_id, RECENT_SCORES_LIMIT).await?,
+        )?;
+
+        Ok(PlayerProfile {
+            player,
+            region_ancestors,
+            categories,
+            awards,
+            site_champ_reigns,
+            recent_scores,
+        })
+    }
+}
@@ -163,6 +163,111 @@ impl Regions {
         return sqlx::query_scalar(const_format::formatcp!("WITH RECURSIVE ancestors AS (SELECT id, parent_id FROM {table_name} WHERE id = $1 UNION SELECT e.id, e.parent_id FROM {table_name} e INNER JOIN ancestors s ON s.parent_id = e.id) SELECT id FROM ancestors;", table_name = Regions::TABLE_NAME)).bind(id).fetch_all(executor).await.map_err(| e | EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
 
+    /// Every `(region_id, container_id)` pair, where the container is the region itself,
+    /// one of its ancestors, or a group it belongs to through itself or an ancestor
+    pub async fn get_containers(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<(i32, i32)>, FinalErrorResponse> {
+        return sqlx::query_as(const_format::formatcp!(
+            r#"
+            WITH RECURSIVE containers AS (
+                SELECT id AS region_id, id AS container_id FROM {regions_table}
+                UNION
+                    SELECT containers.region_id, parents.container_id
+                    FROM containers
+                INNER JOIN (
+                    SELECT id AS child_id, parent_id AS container_id
+                    FROM {regions_table}
+                    WHERE parent_id IS NOT NULL
+                    UNION ALL
+                    SELECT region_id, group_id FROM region_group_members
+                ) AS parents
+                    ON parents.child_id = containers.container_id
+            ) SELECT region_id, container_id FROM containers;
+            "#,
+            regions_table = Regions::TABLE_NAME
+        ))
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+
+    /// The region itself and every region it contains, through its descendants or the
+    /// members of the groups among them. The inverse of [`Regions::get_containers`]
+    pub async fn get_contained(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<Vec<i32>, FinalErrorResponse> {
+        return sqlx::query_scalar(const_format::formatcp!(
+            r#"
+            WITH RECURSIVE contained AS (
+                SELECT id FROM {regions_table}
+                WHERE id = $1
+                UNION
+                    SELECT children.child_id
+                    FROM contained
+                INNER JOIN (
+                    SELECT id AS child_id, parent_id AS container_id
+                    FROM {regions_table}
+                    WHERE parent_id IS NOT NULL
+                    UNION ALL
+                    SELECT region_id, group_id FROM region_group_members
+                ) AS children
+                    ON children.container_id = contained.id
+            ) SELECT id FROM contained;
+            "#,
+            regions_table = Regions::TABLE_NAME
+        ))
+        .bind(id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+
+    /// Replaces the regions belonging to a country or subnational group
+    pub async fn set_group_members(
+        executor: &mut sqlx::PgConnection,
+        group_id: i32,
+        region_ids: &[i32],
+    ) -> Result<(), FinalErrorResponse> {
+        let region_type: Option<RegionType> = sqlx::query_scalar(const_format::formatcp!(
+            "SELECT region_type FROM {table_name} WHERE id = $1;",
+            table_name = Regions::TABLE_NAME
+        ))
+        .bind(group_id)
+        .fetch_optional(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        match region_type {
+            None => {
+                return Err(EveryReturnedError::InvalidInput.into_final_error("Unknown region"));
+            }
+            Some(RegionType::CountryGroup | RegionType::SubnationalGroup) => (),
+            Some(_) => {
+                return Err(EveryReturnedError::InvalidInput
+                    .into_final_error("Only country and subnational groups have members"));
+            }
+        }
+
+        sqlx::query("DELETE FROM region_group_members WHERE group_id = $1;")
+            .bind(group_id)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        sqlx::query(
+            "INSERT INTO region_group_members (group_id, region_id) SELECT $1, UNNEST($2::INTEGER[]) ON CONFLICT DO NOTHING;",
+        )
+        .bind(group_id)
+        .bind(region_ids)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
     pub async fn get_descendants(
         executor: &mut sqlx::PgConnection,
         id: i32,
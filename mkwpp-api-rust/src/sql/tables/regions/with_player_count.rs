@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::{
     api::errors::{EveryReturnedError, FinalErrorResponse},
     sql::tables::{
@@ -58,6 +60,29 @@ impl RegionsWithPlayerCount {
     }
 }
 
+impl RegionsWithPlayerCount {
+    /// Like `collapse_counts_of_regions`, but groups also get the players of
+    /// their members, which aren't necessarily their children
+    pub fn collapse_counts_into_containers(data: &[Self], containers: &[(i32, i32)]) -> Vec<Self> {
+        let own_counts = data
+            .iter()
+            .map(|x| (x.id, x.player_count))
+            .collect::<HashMap<i32, i32>>();
+
+        let mut counts: HashMap<i32, i32> = HashMap::new();
+        for (region_id, container_id) in containers {
+            *counts.entry(*container_id).or_default() +=
+                own_counts.get(region_id).copied().unwrap_or_default();
+        }
+
+        let mut data = data.to_owned();
+        data.iter_mut()
+            .for_each(|x| x.player_count = counts.get(&x.id).copied().unwrap_or_default());
+
+        data
+    }
+}
+
 // TODO: rewrite more optimally
 fn collapse_counts(
     data: &[RegionsWithPlayerCount],
@@ -85,3 +110,90 @@ fn collapse_counts(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RegionsWithPlayerCount;
+    use crate::sql::tables::regions::RegionType;
+
+    fn region(
+        id: i32,
+        region_type: RegionType,
+        parent_id: Option<i32>,
+        player_count: i32,
+    ) -> RegionsWithPlayerCount {
+        RegionsWithPlayerCount {
+            id,
+            code: id.to_string(),
+            region_type,
+            parent_id,
+            is_ranked: true,
+            player_count,
+        }
+    }
+
+    #[test]
+    fn counts_overlapping_groups_once_per_player() {
+        // World > Europe > {DE > Bavaria, AT, CH, DACH, Alps},
+        // where DACH holds DE, AT and CH, and Alps holds AT and CH
+        let data = [
+            region(1, RegionType::World, None, 0),
+            region(2, RegionType::Continent, Some(1), 0),
+            region(3, RegionType::Country, Some(2), 5),
+            region(4, RegionType::Country, Some(2), 2),
+            region(5, RegionType::Country, Some(2), 1),
+            region(6, RegionType::CountryGroup, Some(2), 0),
+            region(7, RegionType::CountryGroup, Some(2), 0),
+            region(8, RegionType::Subnational, Some(3), 3),
+        ];
+        // As returned by `Regions::get_containers`, every pair only once
+        let containers = [
+            (1, 1),
+            (2, 2),
+            (2, 1),
+            (3, 3),
+            (3, 2),
+            (3, 1),
+            (3, 6),
+            (4, 4),
+            (4, 2),
+            (4, 1),
+            (4, 6),
+            (4, 7),
+            (5, 5),
+            (5, 2),
+            (5, 1),
+            (5, 6),
+            (5, 7),
+            (6, 6),
+            (6, 2),
+            (6, 1),
+            (7, 7),
+            (7, 2),
+            (7, 1),
+            (8, 8),
+            (8, 3),
+            (8, 2),
+            (8, 1),
+            (8, 6),
+        ];
+
+        let counts = RegionsWithPlayerCount::collapse_counts_into_containers(&data, &containers)
+            .into_iter()
+            .map(|x| (x.id, x.player_count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [
+                (1, 11),
+                (2, 11),
+                (3, 8),
+                (4, 2),
+                (5, 1),
+                (6, 11),
+                (7, 3),
+                (8, 3)
+            ]
+        );
+    }
+}
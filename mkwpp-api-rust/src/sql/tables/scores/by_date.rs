@@ -1,4 +1,9 @@
-use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+use sqlx::Row;
+
+use crate::api::{
+    errors::{EveryReturnedError, FinalErrorResponse},
+    v1::decode_row_to_table,
+};
 pub use crate::sql::tables::BasicTableQueries;
 
 pub use super::ScoresByDate;
@@ -18,19 +23,116 @@ impl ScoresByDate {
         executor: &mut sqlx::PgConnection,
         limit: i32,
     ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
-        return Self::order(executor, OrderType::All, limit).await;
+        return Self::order(executor, OrderType::All, None, None, limit).await;
+    }
+
+    pub async fn order_player_by_date(
+        executor: &mut sqlx::PgConnection,
+        player_id: i32,
+        limit: i32,
+    ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
+        return Self::order(executor, OrderType::All, Some(player_id), None, limit).await;
+    }
+
+    /// A player's times, most recently accepted first, along with when they were accepted.
+    /// Times added without a submission fall back to the day they were set on
+    pub async fn order_player_by_acceptance(
+        executor: &mut sqlx::PgConnection,
+        player_id: i32,
+        limit: i32,
+    ) -> Result<Vec<(Self, chrono::DateTime<chrono::Utc>)>, FinalErrorResponse> {
+        sqlx::query(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../db/queries/player_by_acceptance.sql"
+        )))
+        .bind(limit)
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .into_iter()
+        .map(|row| {
+            let accepted_at = row
+                .try_get("accepted_at")
+                .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+            Ok((decode_row_to_table(row)?, accepted_at))
+        })
+        .collect()
     }
 
     pub async fn order_records_by_date(
         executor: &mut sqlx::PgConnection,
         limit: i32,
     ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
-        return Self::order(executor, OrderType::Records, limit).await;
+        return Self::order(executor, OrderType::Records, None, None, limit).await;
+    }
+
+    /// Records set by players from any of the given regions
+    pub async fn order_region_records_by_date(
+        executor: &mut sqlx::PgConnection,
+        region_ids: &[i32],
+        limit: i32,
+    ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
+        return Self::order(executor, OrderType::Records, None, Some(region_ids), limit).await;
+    }
+
+    /// A single score, in the same shape as the lists above
+    pub async fn get_score(
+        executor: &mut sqlx::PgConnection,
+        score_id: i32,
+    ) -> Result<Self, FinalErrorResponse> {
+        let row = sqlx::query(
+            r#"
+                SELECT
+                    scores.id AS s_id,
+                    value, category,
+                    is_lap, track_id,
+                    date, players.id, name,
+                    alias, region_id
+                FROM scores
+                LEFT JOIN players ON scores.player_id = players.id
+                WHERE scores.id = $1
+            "#,
+        )
+        .bind(score_id)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        decode_row_to_table(row)
+    }
+
+    /// A submission that hasn't been accepted yet, shown as if it were a score
+    pub async fn get_submission(
+        executor: &mut sqlx::PgConnection,
+        submission_id: i32,
+    ) -> Result<Self, FinalErrorResponse> {
+        let row = sqlx::query(
+            r#"
+                SELECT
+                    submissions.id AS s_id,
+                    value, category,
+                    is_lap, track_id,
+                    date, players.id, name,
+                    alias, region_id
+                FROM submissions
+                LEFT JOIN players ON submissions.player_id = players.id
+                WHERE submissions.id = $1
+            "#,
+        )
+        .bind(submission_id)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        decode_row_to_table(row)
     }
 
     async fn order(
         executor: &mut sqlx::PgConnection,
         order_type: OrderType,
+        player_id: Option<i32>,
+        region_ids: Option<&[i32]>,
         limit: i32,
     ) -> Result<Vec<sqlx::postgres::PgRow>, FinalErrorResponse> {
         return sqlx::query(include_str!(concat!(
@@ -39,6 +141,8 @@ impl ScoresByDate {
         )))
         .bind(limit)
         .bind(order_type == OrderType::Records)
+        .bind(player_id)
+        .bind(region_ids)
         .fetch_all(executor)
         .await
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
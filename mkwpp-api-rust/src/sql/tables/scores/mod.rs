@@ -2,16 +2,22 @@ pub mod by_date;
 pub mod country_rankings;
 pub mod matchup;
 pub mod rankings;
+pub mod record_holders;
 pub mod timesets;
 pub mod timesheet;
 pub mod with_player;
 
 use crate::{
     api::errors::{EveryReturnedError, FinalErrorResponse},
+    app_state::live::{LiveEvent, LiveEventKind},
     custom_serde::DateAsTimestampNumber,
+    mail::notifications,
     sql::tables::{BasicTableQueries, Category, players::players_basic::PlayersBasic},
+    webhooks,
 };
 
+use record_holders::RecordHolder;
+
 #[either_field::make_template(
     GenStructs: true,
     DeleteTemplate: true,
@@ -93,6 +99,38 @@ pub struct ScoresTemplate {
 
 impl super::BasicTableQueries for Scores {
     const TABLE_NAME: &'static str = "scores";
+
+    async fn delete_by_id(
+        id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<sqlx::postgres::PgQueryResult, FinalErrorResponse> {
+        let (track_id, is_lap) = Self::get_chart_from_id(id, executor).await?;
+
+        sqlx::query("UPDATE submissions SET score_id = NULL WHERE score_id = $1;")
+            .bind(id)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        sqlx::query("DELETE FROM edit_submissions WHERE score_id = $1;")
+            .bind(id)
+            .execute(&mut *executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        let result = sqlx::query(const_format::formatcp!(
+            "DELETE FROM {table_name} WHERE id = $1;",
+            table_name = Scores::TABLE_NAME
+        ))
+        .bind(id)
+        .execute(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Self::update_was_wr(track_id, Category::Unres, is_lap, executor).await?;
+
+        Ok(result)
+    }
 }
 
 impl Scores {
@@ -108,6 +146,8 @@ impl Scores {
             .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
 
+    /// Returns the score's id, and the live feed events for the caller to publish
+    /// once its transaction commits
     pub async fn insert_or_edit(
         id: Option<i32>,
         value: i32,
@@ -120,14 +160,27 @@ impl Scores {
         ghost_link: Option<String>,
         comment: Option<String>,
         admin_note: Option<String>,
+        live_feed_open: bool,
         executor: &mut sqlx::PgConnection,
-    ) -> Result<sqlx::postgres::PgQueryResult, FinalErrorResponse> {
-        match id {
+    ) -> Result<(i32, Vec<LiveEvent>), FinalErrorResponse> {
+        let previous_chart = match id {
+            None => None,
+            Some(id) => Some(Self::get_chart_from_id(id, executor).await?),
+        };
+
+        // A new or edited time can only take records on the chart it ends up on
+        let holders_before =
+            match live_feed_open || RecordHolder::anyone_listening(executor).await? {
+                true => Some(RecordHolder::get_for_chart(executor, track_id, is_lap).await?),
+                false => None,
+            };
+
+        let score_id: i32 = match id {
             None => {
-                sqlx::query(const_format::formatcp!("INSERT INTO {table_name} (value, category, is_lap, player_id, track_id, date, video_link, ghost_link, comment, admin_note) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10);", table_name = Scores::TABLE_NAME))
+                sqlx::query_scalar(const_format::formatcp!("INSERT INTO {table_name} (value, category, is_lap, player_id, track_id, date, video_link, ghost_link, comment, admin_note) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id;", table_name = Scores::TABLE_NAME))
             }
             Some(id) => {
-                sqlx::query(const_format::formatcp!("UPDATE {table_name} SET (value, category, is_lap, player_id, track_id, date, video_link, ghost_link, comment, admin_note) = ($2, $3, $4, $5, $6, $7, $8, $9, $10, $11) WHERE id = $1;", table_name = Scores::TABLE_NAME)).bind(id)
+                sqlx::query_scalar(const_format::formatcp!("UPDATE {table_name} SET (value, category, is_lap, player_id, track_id, date, video_link, ghost_link, comment, admin_note) = ($2, $3, $4, $5, $6, $7, $8, $9, $10, $11) WHERE id = $1 RETURNING id;", table_name = Scores::TABLE_NAME)).bind(id)
 
             }
         }
@@ -141,11 +194,71 @@ impl Scores {
         .bind(ghost_link)
         .bind(comment)
         .bind(admin_note)
-        .execute(&mut *executor).await.map_err(| e | EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+        .fetch_one(&mut *executor).await.map_err(| e | EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if let Some(previous_chart) = previous_chart
+            && previous_chart != (track_id, is_lap)
+        {
+            Self::update_was_wr(
+                previous_chart.0,
+                Category::Unres,
+                previous_chart.1,
+                executor,
+            )
+            .await?;
+        }
 
-        Self::update_was_wr(track_id, category, is_lap, executor).await
+        Self::update_was_wr(track_id, Category::Unres, is_lap, executor).await?;
+
+        let mut events = Vec::new();
+
+        if let Some(holders_before) = holders_before {
+            let holders_after = RecordHolder::get_for_chart(executor, track_id, is_lap).await?;
+            let changes = RecordHolder::changes(&holders_before, &holders_after);
+            notifications::send_or_log(executor, "lost record", async |executor| {
+                notifications::records_lost(executor, track_id, is_lap, &changes).await
+            })
+            .await;
+            webhooks::records_set(executor, track_id, is_lap, &changes).await?;
+
+            // Edits only show up on the stream when they take a record
+            if live_feed_open && (id.is_none() || !changes.is_empty()) {
+                let score = ScoresByDate::get_score(executor, score_id).await?;
+                if id.is_none() {
+                    events.push(LiveEvent {
+                        kind: LiveEventKind::Score,
+                        data: score.clone(),
+                    });
+                }
+                if !changes.is_empty() {
+                    events.push(LiveEvent {
+                        kind: LiveEventKind::Record,
+                        data: score,
+                    });
+                }
+            }
+        }
+
+        Ok((score_id, events))
+    }
+
+    async fn get_chart_from_id(
+        id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<(i32, bool), FinalErrorResponse> {
+        return sqlx::query_as(const_format::formatcp!(
+            "SELECT track_id, is_lap FROM {table_name} WHERE id = $1;",
+            table_name = Scores::TABLE_NAME
+        ))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?
+        .ok_or(EveryReturnedError::InvalidInput.into_final_error("Unknown score"));
     }
 
+    /// Passing `Category::Unres` recalculates every category of the chart,
+    /// as a time in a lower category also counts towards the higher ones
     pub async fn update_was_wr(
         track_id: i32,
         category: Category,
@@ -177,6 +290,61 @@ impl Scores {
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
     }
 
+    /// Returns the amount of charts on which the player currently holds the record,
+    /// and the amount of records the player has ever set, for the given category.
+    /// Like for `was_wr`, times from lower categories count towards higher ones
+    pub async fn count_player_records(
+        player_id: i32,
+        category: Category,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<(i64, i64), FinalErrorResponse> {
+        return sqlx::query_as(
+            r#"
+            SELECT
+                (
+                    SELECT COUNT(*)
+                    FROM (
+                        SELECT track_id, is_lap, MIN(value) AS value
+                        FROM scores
+                        WHERE category <= $2
+                        GROUP BY track_id, is_lap
+                    ) AS records
+                    WHERE EXISTS (
+                        SELECT 1
+                        FROM scores
+                        WHERE
+                            scores.player_id = $1 AND
+                            scores.category <= $2 AND
+                            scores.track_id = records.track_id AND
+                            scores.is_lap = records.is_lap AND
+                            scores.value = records.value
+                    )
+                ) AS records_held,
+                (
+                    SELECT COUNT(*)
+                    FROM scores AS og
+                    WHERE
+                        og.player_id = $1 AND
+                        og.category <= $2 AND
+                        og.value = (
+                            SELECT MIN(inn.value)
+                            FROM scores AS inn
+                            WHERE
+                                inn.track_id = og.track_id AND
+                                inn.is_lap = og.is_lap AND
+                                inn.category <= $2 AND
+                                inn.date <= og.date
+                        )
+                ) AS records_ever_held;
+            "#,
+        )
+        .bind(player_id)
+        .bind(category)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e));
+    }
+
     pub async fn get_from_id(
         id: i32,
         executor: &mut sqlx::PgConnection,
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    api::errors::{EveryReturnedError, FinalErrorResponse},
+    sql::tables::{Category, regions::RegionType},
+};
+
+/// The holder of a record in one category and region of a chart.
+/// A time in a lower category also counts towards the higher ones
+#[derive(Debug, sqlx::FromRow)]
+pub struct RecordHolder {
+    pub category: Category,
+    pub region_id: i32,
+    pub region_code: String,
+    pub region_type: RegionType,
+    pub player_id: i32,
+    pub player_name: String,
+    pub value: i32,
+}
+
+/// A record that got beaten, or set for the first time in a region
+pub struct RecordChange<'a> {
+    pub category: Category,
+    /// Everyone tied on the new record
+    pub holders: Vec<&'a RecordHolder>,
+    /// Everyone who held the record before, empty for a first record
+    pub previous: Vec<&'a RecordHolder>,
+}
+
+impl RecordChange<'_> {
+    pub fn value(&self) -> i32 {
+        self.holders[0].value
+    }
+
+    pub fn region(&self) -> &RecordHolder {
+        self.holders[0]
+    }
+
+    /// Holders of the old record who don't tie the new one
+    pub fn lost_by(&self) -> impl Iterator<Item = &RecordHolder> {
+        self.previous
+            .iter()
+            .filter(|old| !self.holders.iter().any(|x| x.player_id == old.player_id))
+            .copied()
+    }
+}
+
+/// Holders before and after, for one category and region
+type HolderSnapshots<'a> = (Vec<&'a RecordHolder>, Vec<&'a RecordHolder>);
+
+impl RecordHolder {
+    /// Whether anything consumes record changes, so that the two extra
+    /// queries around every new time can be skipped otherwise
+    pub async fn anyone_listening(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<bool, FinalErrorResponse> {
+        sqlx::query_scalar(
+            r#"
+                SELECT
+                    EXISTS(
+                        SELECT 1 FROM notification_preferences
+                        JOIN users ON users.id = notification_preferences.user_id
+                        WHERE notification_preferences.lost_records AND users.player_id IS NOT NULL
+                    ) OR EXISTS(
+                        SELECT 1 FROM webhooks
+                        WHERE is_active AND events && '{new_wr, new_regional_record}'::webhook_event[]
+                    )
+            "#,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Current holders of every regional record on a chart, ties included
+    pub async fn get_for_chart(
+        executor: &mut sqlx::PgConnection,
+        track_id: i32,
+        is_lap: bool,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                WITH RECURSIVE player_regions AS (
+                    SELECT players.id AS player_id, regions.id AS region_id, regions.parent_id
+                    FROM players
+                    JOIN regions ON regions.id = players.region_id
+                    WHERE players.id IN (
+                        SELECT player_id FROM scores WHERE track_id = $1 AND is_lap = $2
+                    )
+                    UNION
+                    SELECT player_regions.player_id, regions.id, regions.parent_id
+                    FROM player_regions
+                    JOIN regions ON regions.id = player_regions.parent_id
+                ), best AS (
+                    SELECT
+                        categories.category,
+                        player_regions.region_id,
+                        scores.player_id,
+                        MIN(scores.value) AS value
+                    FROM unnest(enum_range(NULL::category)) AS categories(category)
+                    JOIN scores ON scores.category <= categories.category
+                    JOIN player_regions ON player_regions.player_id = scores.player_id
+                    WHERE
+                        scores.track_id = $1 AND
+                        scores.is_lap = $2
+                    GROUP BY categories.category, player_regions.region_id, scores.player_id
+                ), ranked AS (
+                    SELECT
+                        best.*,
+                        RANK() OVER (PARTITION BY best.category, best.region_id ORDER BY best.value) AS rank
+                    FROM best
+                )
+                SELECT
+                    ranked.category,
+                    ranked.region_id,
+                    regions.code AS region_code,
+                    regions.region_type,
+                    ranked.player_id,
+                    players.name AS player_name,
+                    ranked.value
+                FROM ranked
+                JOIN regions ON regions.id = ranked.region_id
+                JOIN players ON players.id = ranked.player_id
+                WHERE ranked.rank = 1
+            "#,
+        )
+        .bind(track_id)
+        .bind(is_lap)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Every record that was improved between the two snapshots of a chart
+    pub fn changes<'a>(before: &'a [Self], after: &'a [Self]) -> Vec<RecordChange<'a>> {
+        let mut grouped: BTreeMap<(Category, i32), HolderSnapshots> = BTreeMap::new();
+        for holder in before {
+            grouped
+                .entry((holder.category, holder.region_id))
+                .or_default()
+                .0
+                .push(holder);
+        }
+        for holder in after {
+            grouped
+                .entry((holder.category, holder.region_id))
+                .or_default()
+                .1
+                .push(holder);
+        }
+
+        grouped
+            .into_iter()
+            .filter_map(|((category, _), (previous, holders))| {
+                let value = holders.first()?.value;
+                match previous.first() {
+                    Some(old) if old.value <= value => None,
+                    _ => Some(RecordChange {
+                        category,
+                        holders,
+                        previous,
+                    }),
+                }
+            })
+            .collect()
+    }
+}
@@ -93,7 +93,8 @@ enum TimesetOutput {
         players_in_region: Vec<i32>,
         region_found_players: Vec<i32>,
         region_ever_found: Vec<bool>,
-        region_id_to_index: HashMap<i32, usize>,
+        /// Groups can overlap, so a region can count towards several of them
+        region_id_to_index: HashMap<i32, Vec<usize>>,
     },
 }
 
@@ -140,11 +141,6 @@ impl<K: ValidTimesetItem> Timeset<K> {
                 return Err(EveryReturnedError::InvalidInput
                     .into_final_error("There are no Martian players yet!"));
             }
-            RegionType::CountryGroup | RegionType::SubnationalGroup => {
-                return Err(EveryReturnedError::InvalidInput.into_final_error(
-                    "Come back when we'll have figured out how to implement this.",
-                ));
-            }
             x => x,
         };
 
@@ -160,11 +156,13 @@ impl<K: ValidTimesetItem> Timeset<K> {
                 .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?,
         )?;
 
-        let all_regions = RegionsWithPlayerCount::collapse_counts_of_regions(&all_regions)
-            .await
-            .into_iter()
-            .filter(|x| x.player_count != 0)
-            .collect::<Vec<RegionsWithPlayerCount>>();
+        let containers = Regions::get_containers(&mut executor).await?;
+
+        let all_regions =
+            RegionsWithPlayerCount::collapse_counts_into_containers(&all_regions, &containers)
+                .into_iter()
+                .filter(|x| x.player_count != 0)
+                .collect::<Vec<RegionsWithPlayerCount>>();
 
         let mut valid_regions: Vec<(i32, i32)> = all_regions
             .clone()
@@ -178,15 +176,12 @@ impl<K: ValidTimesetItem> Timeset<K> {
         let (valid_regions, valid_regions_player_counts): (Vec<i32>, Vec<i32>) =
             valid_regions.into_iter().unzip();
 
-        let mut hashmap = HashMap::new();
-        for region in all_regions {
-            let ancestors = Regions::get_ancestors(&mut executor, region.id).await?;
-            for ancestor in ancestors {
-                if let Some(v) = valid_regions.iter().position(|x| *x == ancestor) {
-                    if let Some(v) = hashmap.insert(region.id, v) {
-                        panic!("The value being here should be impossible! {v}");
-                    }
-                }
+        // A player counts for every ranked region above theirs and every group
+        // those belong to, so sibling groups sharing a region all get the player
+        let mut hashmap: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (region_id, container_id) in containers {
+            if let Some(index) = valid_regions.iter().position(|x| *x == container_id) {
+                hashmap.entry(region_id).or_default().push(index);
             }
         }
 
@@ -1057,44 +1052,48 @@ impl<K: ValidTimesetItem> Timeset<K> {
                 } => 'value_assignment: {
                     let region_id = time_data.get_player_region_id();
 
-                    let region_index = match region_id_to_index.get(&region_id) {
+                    let region_indexes = match region_id_to_index.get(&region_id) {
                         None => break 'value_assignment,
-                        Some(v) => v.to_owned(),
+                        Some(v) => v,
                     };
 
-                    match *per_region_players == 0 {
-                        true => {
-                            if region_found_players[region_index] == players_in_region[region_index]
-                            {
-                                break 'value_assignment;
-                            }
+                    for &region_index in region_indexes {
+                        match *per_region_players == 0 {
+                            true => {
+                                if region_found_players[region_index]
+                                    == players_in_region[region_index]
+                                {
+                                    continue;
+                                }
 
-                            region_found_players[region_index] += 1;
-                            region_ever_found[region_index] = true;
-                            if region_found_players[region_index] == players_in_region[region_index]
-                            {
-                                has_found_all_times = region_found_players
-                                    .iter()
-                                    .zip(players_in_region.iter())
-                                    .all(|(x, y)| *x == *y);
-                            }
-                        }
-                        false => {
-                            if region_found_players[region_index] == *per_region_players {
-                                break 'value_assignment;
+                                region_found_players[region_index] += 1;
+                                region_ever_found[region_index] = true;
+                                if region_found_players[region_index]
+                                    == players_in_region[region_index]
+                                {
+                                    has_found_all_times = region_found_players
+                                        .iter()
+                                        .zip(players_in_region.iter())
+                                        .all(|(x, y)| *x == *y);
+                                }
                             }
+                            false => {
+                                if region_found_players[region_index] == *per_region_players {
+                                    continue;
+                                }
 
-                            region_found_players[region_index] += 1;
-                            region_ever_found[region_index] = true;
-                            if region_found_players[region_index] == *per_region_players {
-                                has_found_all_times = region_found_players
-                                    .iter()
-                                    .all(|x| *x == *per_region_players);
+                                region_found_players[region_index] += 1;
+                                region_ever_found[region_index] = true;
+                                if region_found_players[region_index] == *per_region_players {
+                                    has_found_all_times = region_found_players
+                                        .iter()
+                                        .all(|x| *x == *per_region_players);
+                                }
                             }
                         }
-                    }
 
-                    region_rank_sums[region_index] += last_rank as f64;
+                        region_rank_sums[region_index] += last_rank as f64;
+                    }
                 }
 
                 TimesetOutput::AverageFinishCharts {
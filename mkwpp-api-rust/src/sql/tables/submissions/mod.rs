@@ -143,7 +143,7 @@ impl Submissions {
         data: SubmissionCreation,
         add_admin_note: bool,
         executor: &mut sqlx::PgConnection,
-    ) -> Result<sqlx::postgres::PgQueryResult, FinalErrorResponse> {
+    ) -> Result<i32, FinalErrorResponse> {
         match (data.submission_id, add_admin_note, data.reviewer_id) {
             (_, false, Some(_)) => {
                 return Err(EveryReturnedError::InsufficientPermissions
@@ -153,7 +153,7 @@ impl Submissions {
                 return Err(EveryReturnedError::InvalidInput
                     .into_final_error("reviewer_id cannot be Some() on first submission"));
             }
-            (None, false, None) => sqlx::query(
+            (None, false, None) => sqlx::query_scalar(
                 r#"
                 INSERT INTO
                     submissions 
@@ -162,10 +162,11 @@ impl Submissions {
                     player_id, track_id, date,
                     video_link, ghost_link, comment,
                     submitter_id, submitter_note
-                ) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11);
+                ) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                RETURNING id;
                 "#,
             ),
-            (Some(id), false, None) => sqlx::query(
+            (Some(id), false, None) => sqlx::query_scalar(
                 r#"
                 UPDATE
                     submissions 
@@ -176,10 +177,11 @@ impl Submissions {
                     video_link = $8, ghost_link = $9,
                     comment = $10, submitter_note = $12
                 WHERE id = $1
+                RETURNING id
                 "#,
             )
             .bind(id),
-            (None, true, None) => sqlx::query(
+            (None, true, None) => sqlx::query_scalar(
                 r#"
                     INSERT INTO
                         submissions 
@@ -189,7 +191,8 @@ impl Submissions {
                         video_link, ghost_link, comment,
                         submitter_id, submitter_note,
                         admin_note
-                    ) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12);
+                    ) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                    RETURNING id;
                     "#,
             ),
             (Some(id), true, None) => {
@@ -201,7 +204,7 @@ impl Submissions {
                         EveryReturnedError::InvalidInput.into_final_error("Partially missing data")
                     );
                 }
-                sqlx::query(
+                sqlx::query_scalar(
                     r#"
                     UPDATE
                         submissions 
@@ -214,6 +217,7 @@ impl Submissions {
                         admin_note = $13, reviewer_note = $14,
                         status = $15
                     WHERE id = $1
+                    RETURNING id
                     "#,
                 )
                 .bind(id)
@@ -228,7 +232,7 @@ impl Submissions {
                     );
                 }
 
-                sqlx::query(
+                sqlx::query_scalar(
                     r#"
                     UPDATE
                         submissions 
@@ -242,6 +246,7 @@ impl Submissions {
                         status = $16, reviewer_id = $2,
                         reviewed_at = NOW()
                     WHERE id = $1
+                    RETURNING id
                     "#,
                 )
                 .bind(id)
@@ -262,11 +267,27 @@ impl Submissions {
         .bind(data.admin_note)
         .bind(data.reviewer_note)
         .bind(data.status)
-        .execute(executor)
+        .fetch_one(executor)
         .await
         .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
     }
 
+    /// Links an accepted submission to the score it created
+    pub async fn set_score_id(
+        id: i32,
+        score_id: i32,
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<(), FinalErrorResponse> {
+        sqlx::query("UPDATE submissions SET score_id = $2 WHERE id = $1")
+            .bind(id)
+            .bind(score_id)
+            .execute(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(())
+    }
+
     pub async fn get_submission_by_id(
         id: i32,
         executor: &mut sqlx::PgConnection,
@@ -0,0 +1,38 @@
+use std::{sync::LazyLock, time::Duration};
+
+use crate::api::errors::{EveryReturnedError, FinalErrorResponse};
+
+/// Redirects followed before a delivery counts as failed
+const MAX_REDIRECTS: usize = 5;
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent("mkwpp-api-rust")
+        .timeout(Duration::from_secs(crate::ENV_VARS.webhook_timeout))
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .build()
+        .expect("Couldn't build the webhook HTTP client")
+});
+
+/// POSTs a JSON body and returns the status code of the response.
+/// Plain `http://` URLs are accepted so that local stub servers work
+pub async fn post_json(
+    url: &url::Url,
+    headers: &[(&str, String)],
+    body: &str,
+) -> Result<u16, FinalErrorResponse> {
+    let mut request = CLIENT
+        .post(url.as_str())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(String::from(body));
+    for (key, value) in headers {
+        request = request.header(*key, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| EveryReturnedError::SendingWebhook.into_final_error(e))?;
+
+    Ok(response.status().as_u16())
+}
@@ -0,0 +1,665 @@
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use rand::Rng;
+
+use crate::{
+    ENV_VARS,
+    api::{
+        errors::{EveryReturnedError, FinalErrorResponse},
+        v1::auth::submissions::SubmissionCreation,
+    },
+    custom_serde::DateAsTimestampNumber,
+    mail::notifications::{category_name, chart_name, format_time},
+    sql::tables::{Category, regions::RegionType, scores::record_holders::RecordChange},
+};
+
+mod http;
+
+/// Deliveries attempted by the worker on each tick
+const BATCH_SIZE: i64 = 25;
+
+#[derive(sqlx::Type, serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone, Copy)]
+#[sqlx(type_name = "webhook_event", rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    NewWr,
+    NewRegionalRecord,
+    NewSubmission,
+    BlogPostPublished,
+}
+
+/// Discord only accepts its own message bodies, so those webhooks get a plain
+/// text summary of the event instead of the generic JSON payload
+#[derive(
+    sqlx::Type, serde::Serialize, serde::Deserialize, Debug, Default, PartialEq, Clone, Copy,
+)]
+#[sqlx(type_name = "webhook_format", rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookFormat {
+    #[default]
+    Generic,
+    Discord,
+}
+
+#[derive(sqlx::Type, serde::Serialize, Debug, PartialEq, Clone)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// An endpoint registered by an admin. The secret is only shown once, when created
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhooks {
+    pub id: i32,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub format: WebhookFormat,
+    pub is_active: bool,
+    pub created_by: Option<i32>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveries {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_code: Option<i32>,
+    pub last_error: Option<String>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub next_attempt: chrono::DateTime<chrono::Utc>,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedWebhook {
+    pub id: i32,
+    /// Key for the `X-Webhook-Signature` HMAC-SHA256
+    pub secret: String,
+}
+
+fn parse_url(url: &str) -> Result<url::Url, FinalErrorResponse> {
+    let parsed =
+        url::Url::parse(url).map_err(|e| EveryReturnedError::InvalidInput.into_final_error(e))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(parsed),
+        _ => Err(EveryReturnedError::InvalidInput
+            .into_final_error("Webhook URLs must be absolute http or https URLs")),
+    }
+}
+
+impl Webhooks {
+    pub async fn get_all(
+        executor: &mut sqlx::PgConnection,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(
+            "SELECT id, url, events, format, is_active, created_by, created FROM webhooks ORDER BY id",
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    pub async fn create(
+        executor: &mut sqlx::PgConnection,
+        url: &str,
+        events: &[WebhookEvent],
+        format: WebhookFormat,
+        created_by: i32,
+    ) -> Result<CreatedWebhook, FinalErrorResponse> {
+        parse_url(url)?;
+
+        let mut secret_bytes = [0u8; 32];
+        rand::rng().fill(&mut secret_bytes);
+        let secret = data_encoding::HEXLOWER.encode(&secret_bytes);
+
+        let id = sqlx::query_scalar(
+            "INSERT INTO webhooks (url, secret, events, format, created_by) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        )
+        .bind(url)
+        .bind(&secret)
+        .bind(events)
+        .bind(format)
+        .bind(created_by)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        Ok(CreatedWebhook { id, secret })
+    }
+
+    pub async fn update(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+        url: &str,
+        events: &[WebhookEvent],
+        format: Option<WebhookFormat>,
+        is_active: bool,
+    ) -> Result<(), FinalErrorResponse> {
+        parse_url(url)?;
+
+        let result = sqlx::query(
+            "UPDATE webhooks SET url = $2, events = $3, format = COALESCE($4, format), is_active = $5 WHERE id = $1",
+        )
+        .bind(id)
+        .bind(url)
+        .bind(events)
+        .bind(format)
+        .bind(is_active)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(id)
+            .execute(executor)
+            .await
+            .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload<T: serde::Serialize> {
+    event: WebhookEvent,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    created_at: chrono::DateTime<chrono::Utc>,
+    data: T,
+}
+
+#[derive(serde::Serialize)]
+struct DiscordAllowedMentions {
+    parse: [&'static str; 0],
+}
+
+#[derive(serde::Serialize)]
+struct DiscordPayload<'a> {
+    content: &'a str,
+    allowed_mentions: DiscordAllowedMentions,
+}
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+/// Queues a delivery of the event for every active webhook subscribed to it.
+/// It only gets delivered if the caller's transaction commits
+pub async fn dispatch(
+    executor: &mut sqlx::PgConnection,
+    event: WebhookEvent,
+    data: impl serde::Serialize,
+    summary: &str,
+) -> Result<(), FinalErrorResponse> {
+    let payload = serde_json::to_string(&Payload {
+        event,
+        created_at: chrono::Utc::now(),
+        data,
+    })
+    .map_err(|e| EveryReturnedError::SerializingDataToJSON.into_final_error(e))?;
+
+    // Names come from user input, so nothing in the summary is allowed to ping anyone
+    let content = match summary.char_indices().nth(DISCORD_MAX_LENGTH) {
+        Some((end, _)) => &summary[..end],
+        None => summary,
+    };
+    let discord_payload = serde_json::to_string(&DiscordPayload {
+        content,
+        allowed_mentions: DiscordAllowedMentions { parse: [] },
+    })
+    .map_err(|e| EveryReturnedError::SerializingDataToJSON.into_final_error(e))?;
+
+    sqlx::query(
+        r#"
+            INSERT INTO webhook_deliveries (webhook_id, event, payload)
+            SELECT id, $1, CASE format WHEN 'discord' THEN $3 ELSE $2 END FROM webhooks
+            WHERE is_active AND $1 = ANY(events)
+        "#,
+    )
+    .bind(event)
+    .bind(payload)
+    .bind(discord_payload)
+    .execute(executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordEvent<'a> {
+    track_id: i32,
+    is_lap: bool,
+    categories: Vec<Category>,
+    region_id: i32,
+    region_code: &'a str,
+    player_ids: Vec<i32>,
+    player_names: Vec<&'a str>,
+    value: i32,
+    previous_value: Option<i32>,
+    previous_player_names: Vec<&'a str>,
+}
+
+/// One `newWr` or `newRegionalRecord` event per region, listing every category
+/// the record was set in. The holders are taken from the lowest category
+pub async fn records_set(
+    executor: &mut sqlx::PgConnection,
+    track_id: i32,
+    is_lap: bool,
+    changes: &[RecordChange<'_>],
+) -> Result<(), FinalErrorResponse> {
+    let mut regions: BTreeMap<i32, Vec<&RecordChange>> = BTreeMap::new();
+    for change in changes {
+        regions
+            .entry(change.region().region_id)
+            .or_default()
+            .push(change);
+    }
+
+    if regions.is_empty() {
+        return Ok(());
+    }
+
+    let track_abbr: String = sqlx::query_scalar("SELECT abbr FROM tracks WHERE id = $1")
+        .bind(track_id)
+        .fetch_one(&mut *executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+    let chart = chart_name(&track_abbr, is_lap);
+
+    for changes in regions.into_values() {
+        let Some(first) = changes.iter().min_by_key(|x| x.category) else {
+            continue;
+        };
+        let region = first.region();
+
+        let (event, record) = match region.region_type {
+            RegionType::World => (WebhookEvent::NewWr, String::from("world record")),
+            _ => (
+                WebhookEvent::NewRegionalRecord,
+                format!("{} record", region.region_code),
+            ),
+        };
+
+        let mut summary = format!(
+            "New {record} on {chart} ({}): {} by {}",
+            changes
+                .iter()
+                .map(|x| category_name(&x.category))
+                .collect::<Vec<_>>()
+                .join(", "),
+            format_time(first.value()),
+            first
+                .holders
+                .iter()
+                .map(|x| x.player_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        if let Some(previous) = first.previous.first() {
+            summary += &format!(
+                ", previously {} by {}",
+                format_time(previous.value),
+                first
+                    .previous
+                    .iter()
+                    .map(|x| x.player_name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+
+        dispatch(
+            executor,
+            event,
+            RecordEvent {
+                track_id,
+                is_lap,
+                categories: changes.iter().map(|x| x.category).collect(),
+                region_id: region.region_id,
+                region_code: &region.region_code,
+                player_ids: first.holders.iter().map(|x| x.player_id).collect(),
+                player_names: first
+                    .holders
+                    .iter()
+                    .map(|x| x.player_name.as_str())
+                    .collect(),
+                value: first.value(),
+                previous_value: first.previous.first().map(|x| x.value),
+                previous_player_names: first
+                    .previous
+                    .iter()
+                    .map(|x| x.player_name.as_str())
+                    .collect(),
+            },
+            &summary,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmissionEvent<'a> {
+    value: i32,
+    category: Category,
+    is_lap: bool,
+    player_id: i32,
+    track_id: i32,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    date: Option<chrono::NaiveDate>,
+    video_link: Option<&'a str>,
+    ghost_link: Option<&'a str>,
+    comment: Option<&'a str>,
+}
+
+pub async fn submission_created(
+    executor: &mut sqlx::PgConnection,
+    submission: &SubmissionCreation,
+) -> Result<(), FinalErrorResponse> {
+    let (track_abbr, player_name): (String, String) = sqlx::query_as(
+        "SELECT (SELECT abbr FROM tracks WHERE id = $1), (SELECT name FROM players WHERE id = $2)",
+    )
+    .bind(submission.track_id)
+    .bind(submission.player_id)
+    .fetch_one(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    dispatch(
+        executor,
+        WebhookEvent::NewSubmission,
+        SubmissionEvent {
+            value: submission.value,
+            category: submission.category,
+            is_lap: submission.is_lap,
+            player_id: submission.player_id,
+            track_id: submission.track_id,
+            date: submission.date,
+            video_link: submission.video_link.as_deref(),
+            ghost_link: submission.ghost_link.as_deref(),
+            comment: submission.comment.as_deref(),
+        },
+        &format!(
+            "New submission by {player_name}: {} on {} ({})",
+            format_time(submission.value),
+            chart_name(&track_abbr, submission.is_lap),
+            category_name(&submission.category),
+        ),
+    )
+    .await
+}
+
+#[derive(serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+struct BlogPostEvent {
+    id: i32,
+    title: String,
+    #[serde(serialize_with = "DateAsTimestampNumber::serialize_as_timestamp")]
+    published_at: chrono::DateTime<chrono::Utc>,
+    author: Option<String>,
+}
+
+/// Blog posts are announced once they are published and their `published_at` has passed,
+/// which also covers posts scheduled for later
+pub async fn blog_posts_published(
+    executor: &mut sqlx::PgConnection,
+) -> Result<(), FinalErrorResponse> {
+    let posts = sqlx::query_as::<_, BlogPostEvent>(
+        r#"
+            UPDATE blog_posts
+            SET announced = TRUE
+            WHERE
+                is_published AND
+                published_at <= NOW() AND
+                NOT announced
+            RETURNING
+                id, title, published_at,
+                (SELECT username FROM users WHERE users.id = blog_posts.author_id) AS author
+        "#,
+    )
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    for post in posts {
+        let summary = match &post.author {
+            Some(author) => format!("New blog post by {author}: {}", post.title),
+            None => format!("New blog post: {}", post.title),
+        };
+        dispatch(executor, WebhookEvent::BlogPostPublished, post, &summary).await?;
+    }
+
+    Ok(())
+}
+
+impl WebhookDeliveries {
+    /// Most recent deliveries first
+    pub async fn get_recent(
+        executor: &mut sqlx::PgConnection,
+        webhook_id: Option<i32>,
+    ) -> Result<Vec<Self>, FinalErrorResponse> {
+        sqlx::query_as(
+            r#"
+                SELECT * FROM webhook_deliveries
+                WHERE $1::INTEGER IS NULL OR webhook_id = $1
+                ORDER BY created DESC
+                LIMIT 100
+            "#,
+        )
+        .bind(webhook_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))
+    }
+
+    /// Puts a failed delivery back in the queue with a fresh set of attempts
+    pub async fn retry(
+        executor: &mut sqlx::PgConnection,
+        id: i32,
+    ) -> Result<(), FinalErrorResponse> {
+        let result = sqlx::query(
+            r#"
+                UPDATE webhook_deliveries
+                SET status = 'pending', attempts = 0, next_attempt = NOW()
+                WHERE id = $1 AND status = 'failed'
+            "#,
+        )
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(EveryReturnedError::NothingChanged.into_final_error(""));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+    id: i32,
+    event: WebhookEvent,
+    payload: String,
+    url: String,
+    secret: String,
+}
+
+impl DueDelivery {
+    /// The signature is an HMAC-SHA256 of `<timestamp>.<body>`, keyed with the webhook's secret
+    fn signature(&self, timestamp: i64) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts any key length");
+        mac.update(format!("{timestamp}.{}", self.payload).as_bytes());
+        format!(
+            "sha256={}",
+            data_encoding::HEXLOWER.encode(&mac.finalize().into_bytes())
+        )
+    }
+
+    async fn deliver(&self) -> Result<u16, FinalErrorResponse> {
+        let url = parse_url(&self.url)?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let event = serde_json::to_value(self.event)
+            .map_err(|e| EveryReturnedError::SerializingDataToJSON.into_final_error(e))?;
+
+        http::post_json(
+            &url,
+            &[
+                ("X-Webhook-Id", self.id.to_string()),
+                (
+                    "X-Webhook-Event",
+                    event.as_str().unwrap_or_default().to_string(),
+                ),
+                ("X-Webhook-Timestamp", timestamp.to_string()),
+                ("X-Webhook-Signature", self.signature(timestamp)),
+            ],
+            &self.payload,
+        )
+        .await
+    }
+}
+
+/// Attempts one batch of due deliveries. Anything but a 2xx response is retried
+/// with exponential backoff until `WEBHOOK_MAX_ATTEMPTS` is reached.
+/// Deliveries are claimed before being sent, so no lock is held during the
+/// requests and a crashed worker only means the delivery is retried later
+async fn process_batch(executor: &mut sqlx::PgConnection) -> Result<(), FinalErrorResponse> {
+    let deliveries: Vec<DueDelivery> = sqlx::query_as(
+        r#"
+            WITH due AS (
+                SELECT webhook_deliveries.id
+                FROM webhook_deliveries
+                JOIN webhooks ON webhooks.id = webhook_deliveries.webhook_id
+                WHERE
+                    webhook_deliveries.status = 'pending' AND
+                    webhook_deliveries.next_attempt <= NOW() AND
+                    webhooks.is_active
+                ORDER BY webhook_deliveries.next_attempt
+                LIMIT $1
+                FOR UPDATE OF webhook_deliveries SKIP LOCKED
+            )
+            UPDATE webhook_deliveries
+            SET
+                attempts = webhook_deliveries.attempts + 1,
+                next_attempt = NOW() + make_interval(secs => $2 * power(2, LEAST(webhook_deliveries.attempts, 16)))
+            FROM due, webhooks
+            WHERE
+                webhook_deliveries.id = due.id AND
+                webhooks.id = webhook_deliveries.webhook_id
+            RETURNING
+                webhook_deliveries.id, webhook_deliveries.event, webhook_deliveries.payload,
+                webhooks.url, webhooks.secret
+        "#,
+    )
+    .bind(BATCH_SIZE)
+    .bind(ENV_VARS.webhook_retry_delay as f64)
+    .fetch_all(&mut *executor)
+    .await
+    .map_err(|e| EveryReturnedError::GettingFromDatabase.into_final_error(e))?;
+
+    for delivery in deliveries {
+        let (response_code, error) = match delivery.deliver().await {
+            Ok(code) if (200..300).contains(&code) => (Some(code), None),
+            Ok(code) => (
+                Some(code),
+                Some(format!("Endpoint responded with status {code}")),
+            ),
+            Err(e) => (None, Some(e.messages())),
+        };
+
+        let query = match error {
+            None => sqlx::query(
+                r#"
+                    UPDATE webhook_deliveries
+                    SET
+                        status = 'delivered',
+                        response_code = $2,
+                        delivered_at = NOW()
+                    WHERE id = $1
+                "#,
+            )
+            .bind(delivery.id)
+            .bind(response_code.map(i32::from)),
+            Some(error) => sqlx::query(
+                r#"
+                    UPDATE webhook_deliveries
+                    SET
+                        response_code = $2,
+                        last_error = $3,
+                        status = CASE
+                            WHEN attempts >= $4 THEN 'failed'::webhook_delivery_status
+                            ELSE 'pending'::webhook_delivery_status
+                        END
+                    WHERE id = $1
+                "#,
+            )
+            .bind(delivery.id)
+            .bind(response_code.map(i32::from))
+            .bind(error)
+            .bind(ENV_VARS.webhook_max_attempts as i32),
+        };
+
+        // The claim already pushed `next_attempt` back, so a lost result only means a resend
+        if let Err(e) = query.execute(&mut *executor).await {
+            println!("Could not record webhook delivery {}: {e}", delivery.id);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn worker() {
+    let mut interval = tokio::time::interval(core::time::Duration::new(
+        crate::ENV_VARS.webhook_interval.max(1),
+        0,
+    ));
+    loop {
+        interval.tick().await;
+        let app_state = crate::app_state::access_app_state().await;
+        let mut executor = {
+            let app_state_guard = app_state.read().await;
+            match app_state_guard.acquire_pg_connection().await {
+                Ok(v) => v,
+                Err(_) => continue,
+            }
+        };
+
+        let _ = process_batch(&mut executor).await;
+    }
+}